@@ -0,0 +1,46 @@
+// how long a layout tick takes on webs the size of a big crawl
+// run with cargo bench --bench layout
+#[allow(dead_code)]
+#[path = "../src/layout.rs"]
+mod layout;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+
+// a random web where everyone follows a handful of others
+fn web(count: usize) -> layout::Layout {
+    let mut layout = layout::Layout::new(-30.0, 30.0);
+    for i in 0..count {
+        // same spiral d3 starts nodes off in
+        let angle = i as f64 * std::f64::consts::PI * (3.0 - 5.0f64.sqrt());
+        let radius = 10.0 * (0.5 + i as f64).sqrt();
+        layout.apply(layout::Change::Add([
+            radius * angle.cos(),
+            radius * angle.sin(),
+        ]));
+    }
+    let mut seed = 0x2545f4914f6cdd1d_u64;
+    for i in 0..count {
+        for _ in 0..8 {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            layout.apply(layout::Change::Link(i, seed as usize % count));
+        }
+    }
+    layout
+}
+
+fn tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick");
+    group.sample_size(20);
+    for count in [10_000, 50_000] {
+        let mut layout = web(count);
+        // the first ticks fling everyone out of the spiral so skip past them
+        layout.tick(30);
+        group.bench_function(count.to_string(), |b| b.iter(|| layout.tick(1)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, tick);
+criterion_main!(benches);
@@ -1,24 +1,56 @@
 use super::*;
-use atrium_api::app::bsky::actor::get_profile;
+use atrium_api::app::bsky::actor::defs::ProfileViewBasicData;
+use atrium_api::app::bsky::actor::defs::{ProfileViewData, ProfileViewDetailedData};
+use atrium_api::app::bsky::actor::{get_profile, get_profiles, search_actors_typeahead};
+use atrium_api::app::bsky::graph::{get_list, get_starter_pack};
+use atrium_api::com::atproto::identity::resolve_handle;
+use atrium_api::types::string::AtIdentifier;
 
 pub struct Stuff;
 
 impl Plugin for Stuff {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Ask>()
-            .add_systems(
-                bevy_egui::EguiPrimaryContextPass,
-                ask.run_if(in_state(Game::Ask)),
-            )
-            .add_systems(Update, check.run_if(in_state(Game::Ask)));
+        app.insert_resource(Ask {
+            recent: recall(),
+            ..default()
+        })
+        .add_systems(
+            bevy_egui::EguiPrimaryContextPass,
+            ask.run_if(in_state(Game::Ask)),
+        )
+        .add_systems(Update, (check, suggest).run_if(in_state(Game::Ask)));
     }
 }
 
-fn ask(mut ctx: bevy_egui::EguiContexts, mut ask: ResMut<Ask>) {
+fn ask(mut ctx: bevy_egui::EguiContexts, mut ask: ResMut<Ask>, time: Res<Time>) {
     use bevy_egui::egui;
+    let avatars: Vec<_> = ask
+        .matches
+        .iter()
+        .map(|(_, avatar)| {
+            avatar
+                .as_ref()
+                .map(|avatar| ctx.add_image(avatar.clone_weak()))
+        })
+        .collect();
     let Ok(ctx) = ctx.ctx_mut() else { return };
+    // recently viewed handles until there's something to search for
+    let options: Vec<(String, Option<String>)> = match ask.buf.trim().is_empty() {
+        true => ask
+            .recent
+            .iter()
+            .map(|handle| (handle.clone(), None))
+            .collect(),
+        false => ask
+            .matches
+            .iter()
+            .map(|(actor, _)| (actor.handle.to_string(), actor.display_name.clone()))
+            .collect(),
+    };
+    let mut pick = None;
     egui::CentralPanel::default().show(ctx, |ui| {
         ui.vertical_centered_justified(|ui| {
+            let body = ui.style().text_styles[&egui::TextStyle::Body].clone();
             let size = &mut ui
                 .style_mut()
                 .text_styles
@@ -31,47 +63,495 @@ fn ask(mut ctx: bevy_egui::EguiContexts, mut ask: ResMut<Ask>) {
             *width *= 2.0;
             let width = *width;
             ui.allocate_space(egui::Vec2::new(0.0, (ui.available_height() - size) / 2.0));
+            let field = egui::Id::new("handle");
+            // the arrow keys would otherwise move the cursor
+            if ctx.memory(|memory| memory.has_focus(field)) && !options.is_empty() {
+                ui.input_mut(|input| {
+                    if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) {
+                        ask.highlight =
+                            Some(ask.highlight.map_or(0, |i| (i + 1).min(options.len() - 1)))
+                    }
+                    if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) {
+                        ask.highlight = ask.highlight.and_then(|i| i.checked_sub(1))
+                    }
+                });
+            }
             ui.horizontal(|ui| {
                 ui.add_space((ui.available_width() - width) / 2.0);
-                if (ui
-                    .add(
-                        egui::TextEdit::singleline(&mut ask.buf)
-                            .hint_text("enter your bsky handle"),
-                    )
-                    .lost_focus()
-                    && ui.input(|input| input.key_pressed(egui::Key::Enter))
-                    || ui.button("go").clicked())
-                    && let Ok(actor) = ask
-                        .buf
-                        .parse::<atrium_api::types::string::AtIdentifier>()
-                        .or_else(|_| (ask.buf.clone() + ".bsky.social").parse())
+                let res = ui.add(
+                    egui::TextEdit::singleline(&mut ask.buf)
+                        .id(field)
+                        .hint_text("enter your bsky handle or a list link"),
+                );
+                if res.changed() {
+                    ask.edited = time.elapsed_secs();
+                    ask.highlight = None;
+                }
+                if res.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter))
+                    || ui.button("go").clicked()
                 {
-                    ask.task = Some(
-                        bevy::tasks::IoTaskPool::get().spawn(Compat::new(
-                            CLIENT.service.app.bsky.actor.get_profile(
-                                get_profile::ParametersData {
-                                    actor: actor.clone(),
-                                }
-                                .into(),
-                            ),
-                        )),
-                    );
+                    pick = Some(
+                        ask.highlight
+                            .and_then(|i| options.get(i))
+                            .map_or_else(|| ask.buf.clone(), |(handle, _)| handle.clone()),
+                    )
                 }
             });
+            if ask.task.is_some() {
+                ui.spinner();
+            }
             if let Some(err) = ask.err.as_ref() {
                 ui.colored_label(egui::Color32::RED, err);
             }
+            // suggestions are back at normal size
+            ui.style_mut()
+                .text_styles
+                .insert(egui::TextStyle::Body, body.clone());
+            ui.style_mut()
+                .text_styles
+                .insert(egui::TextStyle::Button, body);
+            ui.allocate_ui_with_layout(
+                egui::Vec2::new(width, 0.0),
+                egui::Layout::top_down_justified(egui::Align::Min),
+                |ui| {
+                    for (i, (handle, name)) in options.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if let Some(Some(avatar)) =
+                                avatars.get(i).filter(|_| !ask.buf.trim().is_empty())
+                            {
+                                ui.image(egui::load::SizedTexture::new(*avatar, [24.0, 24.0]));
+                            }
+                            let text = match name.as_deref().filter(|name| !name.is_empty()) {
+                                Some(name) => format!("{name} @{handle}"),
+                                None => format!("@{handle}"),
+                            };
+                            if ui
+                                .selectable_label(ask.highlight == Some(i), text)
+                                .clicked()
+                            {
+                                pick = Some(handle.clone())
+                            }
+                        });
+                    }
+                },
+            );
+            ui.add_space(size);
+            ui.horizontal(|ui| {
+                ui.add_space((ui.available_width() - width) / 2.0);
+                ui.label("with:");
+                let res = ui.add(
+                    egui::TextEdit::singleline(&mut ask.others)
+                        .hint_text("more handles or a list link (optional)"),
+                );
+                if res.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter))
+                    || ui.button("go").clicked()
+                {
+                    pick = Some(ask.buf.clone())
+                }
+            });
+            // each of them gets their follows fetched
+            if !ask.others.trim().is_empty() {
+                ui.small(format!(
+                    "lists here only bring in their first {ROOTS} members"
+                ));
+            }
         })
     });
+    if let Some(input) = pick {
+        ask.buf = input;
+        // no point suggesting what's already been picked
+        ask.queried = ask.buf.clone();
+        ask.highlight = None;
+        let others: Option<Vec<_>> = ask
+            .others
+            .split([',', ' '])
+            .filter(|input| !input.trim().is_empty())
+            .map(parse)
+            .collect();
+        match (parse(&ask.buf), others) {
+            _ if ask.buf.trim().is_empty() => ask.err = Some("enter a handle first".into()),
+            (Some(main), Some(others)) => {
+                ask.err = None;
+                ask.task = Some(
+                    bevy::tasks::IoTaskPool::get().spawn(Compat::new(lookup_all(main, others))),
+                )
+            }
+            _ => ask.err = Some("that doesn't look like a handle".into()),
+        }
+    }
 }
 
 #[derive(Resource, Default)]
 struct Ask {
     buf: String,
+    // anyone else whose follows go in the same web
+    others: String,
     err: Option<String>,
-    task: Option<
-        bevy::tasks::Task<atrium_api::xrpc::Result<get_profile::Output, get_profile::Error>>,
+    task: Option<bevy::tasks::Task<Result<Found, String>>>,
+    matches: Vec<(ProfileViewBasicData, Option<Handle<Image>>)>,
+    typing: Option<
+        bevy::tasks::Task<
+            atrium_api::xrpc::Result<
+                search_actors_typeahead::Output,
+                search_actors_typeahead::Error,
+            >,
+        >,
     >,
+    // when the buffer was last edited and what was last searched for
+    edited: f32,
+    queried: String,
+    highlight: Option<usize>,
+    recent: Vec<String>,
+}
+
+// wait for typing to settle before searching
+const DEBOUNCE: f32 = 0.3;
+const RECENT: &str = "recent.json";
+
+fn recall() -> Vec<String> {
+    auth::path(RECENT)
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+// on wasm these only last as long as the tab
+fn remember(recent: &mut Vec<String>, handle: String) {
+    recent.retain(|other| *other != handle);
+    recent.insert(0, handle);
+    recent.truncate(8);
+    let Some(path) = auth::path(RECENT) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Ok(bytes) = serde_json::to_vec(recent) {
+        let _ = std::fs::write(path, bytes);
+    }
+}
+
+fn suggest(mut ask: ResMut<Ask>, time: Res<Time>, server: Res<AssetServer>) {
+    if let Some(Some(res)) = ask
+        .typing
+        .as_mut()
+        .map(|task| bevy::tasks::block_on(bevy::tasks::futures_lite::future::poll_once(task)))
+    {
+        ask.typing = None;
+        ask.highlight = None;
+        ask.matches = match res {
+            Ok(output) => output
+                .data
+                .actors
+                .into_iter()
+                .map(|actor| {
+                    let avatar = actor.avatar.clone().map(|avatar| {
+                        server.load_with_settings(
+                            avatar,
+                            |s: &mut bevy::image::ImageLoaderSettings| {
+                                s.format = bevy::image::ImageFormatSetting::Guess
+                            },
+                        )
+                    });
+                    (actor.data, avatar)
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+    }
+    if ask.typing.is_some()
+        || ask.task.is_some()
+        || ask.buf == ask.queried
+        || time.elapsed_secs() - ask.edited < DEBOUNCE
+    {
+        return;
+    }
+    ask.queried = ask.buf.clone();
+    let q = ask.buf.trim().trim_start_matches('@').to_owned();
+    if q.len() < 2 {
+        ask.matches.clear();
+        return;
+    }
+    ask.typing = Some(
+        bevy::tasks::IoTaskPool::get().spawn(Compat::new(
+            CLIENT.service.app.bsky.actor.search_actors_typeahead(
+                search_actors_typeahead::ParametersData {
+                    limit: 8.try_into().ok(),
+                    q: Some(q),
+                    term: None,
+                }
+                .into(),
+            ),
+        )),
+    );
+}
+
+// accepts handles with or without the @, dids and profile links
+fn normalise(input: &str) -> Option<AtIdentifier> {
+    let input = input
+        .trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    let input = input.strip_prefix("bsky.app/profile/").unwrap_or(input);
+    let input = input.split(['/', '?', '#']).next()?.trim_start_matches('@');
+    if input.starts_with("did:") {
+        return input.parse().ok();
+    }
+    let mut handle = input.to_lowercase();
+    // bare names are most likely on bsky.social
+    if !handle.contains('.') {
+        handle += ".bsky.social"
+    }
+    handle.parse().ok()
+}
+
+// lists and starter packs count as well as single accounts
+enum Who {
+    Actor(AtIdentifier),
+    Group(Group),
+}
+
+enum Group {
+    // whose record it is, which collection it's in and its key
+    Record(AtIdentifier, &'static str, String),
+    // go.bsky.app links only say where they go once they're followed
+    Short(String),
+}
+
+const LIST: &str = "app.bsky.graph.list";
+const PACK: &str = "app.bsky.graph.starterpack";
+
+// lists and starter packs are either at-uris, bsky.app links or go.bsky.app short links
+fn parse(input: &str) -> Option<Who> {
+    let trimmed = input
+        .trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    if let Some(code) = trimmed
+        .strip_prefix("go.bsky.app/")
+        .filter(|code| !code.is_empty())
+    {
+        return Some(Who::Group(Group::Short(format!(
+            "https://go.bsky.app/{code}"
+        ))));
+    }
+    if let Some(path) = trimmed
+        .strip_prefix("at://")
+        .or_else(|| trimmed.strip_prefix("bsky.app/"))
+    {
+        let parts: Vec<_> = path.split(['/', '?', '#']).collect();
+        match parts[..] {
+            ["profile", actor, "lists", rkey, ..] | [actor, LIST, rkey, ..] => {
+                return Some(Who::Group(Group::Record(
+                    actor.parse().ok()?,
+                    LIST,
+                    rkey.to_owned(),
+                )));
+            }
+            ["starter-pack" | "start", actor, rkey, ..] | [actor, PACK, rkey, ..] => {
+                return Some(Who::Group(Group::Record(
+                    actor.parse().ok()?,
+                    PACK,
+                    rkey.to_owned(),
+                )));
+            }
+            _ => {}
+        }
+    }
+    normalise(input).map(Who::Actor)
+}
+
+// every root has all their follows fetched so big lists would take forever
+const ROOTS: usize = 20;
+
+// roots to crawl from and members to put straight in the web
+type Found = (Vec<ProfileViewDetailedData>, Vec<ProfileViewData>);
+
+async fn lookup_all(main: Who, others: Vec<Who>) -> Result<Found, String> {
+    let mut found = Found::default();
+    match main {
+        Who::Actor(actor) => found.0.push(lookup(actor).await?.data),
+        // a list on its own is just its members and who they follow between them
+        Who::Group(group) => {
+            found.1 = members(group).await?;
+            if found.1.is_empty() {
+                return Err("there's nobody on that list".into());
+            }
+        }
+    }
+    for who in others {
+        let room = ROOTS.saturating_sub(found.0.len());
+        match who {
+            _ if room == 0 => break,
+            Who::Actor(actor) => found.0.push(lookup(actor).await?.data),
+            Who::Group(group) => {
+                let mut members = members(group).await?;
+                members.truncate(room);
+                found.0.extend(detail(members).await?)
+            }
+        }
+    }
+    Ok(found)
+}
+
+const OFFLINE: &str = "couldn't reach bluesky, check your connection and try again";
+
+async fn members(group: Group) -> Result<Vec<ProfileViewData>, String> {
+    let (actor, collection, rkey) = match group {
+        Group::Record(actor, collection, rkey) => (actor, collection, rkey),
+        Group::Short(link) => unshorten(&link).await?,
+    };
+    let mut list = format!("at://{}/{collection}/{rkey}", did(actor).await?.as_str());
+    // starter packs are a list with a bit extra on top
+    if collection == PACK {
+        list = CLIENT
+            .service
+            .app
+            .bsky
+            .graph
+            .get_starter_pack(get_starter_pack::ParametersData { starter_pack: list }.into())
+            .await
+            .map_err(|e| match e {
+                atrium_xrpc::Error::XrpcResponse(_) => "couldn't find that starter pack".to_owned(),
+                _ => OFFLINE.into(),
+            })?
+            .data
+            .starter_pack
+            .data
+            .list
+            .ok_or("that starter pack doesn't have anyone in it")?
+            .data
+            .uri
+    }
+    let mut members = Vec::new();
+    let mut cursor = None;
+    loop {
+        let data = CLIENT
+            .service
+            .app
+            .bsky
+            .graph
+            .get_list(
+                get_list::ParametersData {
+                    cursor,
+                    limit: 100.try_into().ok(),
+                    list: list.clone(),
+                }
+                .into(),
+            )
+            .await
+            .map_err(|e| match e {
+                atrium_xrpc::Error::XrpcResponse(_) => "couldn't find that list".to_owned(),
+                _ => OFFLINE.into(),
+            })?
+            .data;
+        members.extend(data.items.into_iter().map(|item| item.data.subject.data));
+        cursor = data.cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    Ok(members)
+}
+
+// follows the redirect to wherever the short link points
+async fn unshorten(link: &str) -> Result<(AtIdentifier, &'static str, String), String> {
+    let url = reqwest::get(link)
+        .await
+        .map_err(|_| OFFLINE.to_owned())?
+        .url()
+        .to_string();
+    match parse(&url) {
+        Some(Who::Group(Group::Record(actor, collection, rkey))) => Ok((actor, collection, rkey)),
+        _ => Err("that link doesn't go to a list or starter pack".into()),
+    }
+}
+
+// list items don't have follow counts so roots are fetched properly
+async fn detail(members: Vec<ProfileViewData>) -> Result<Vec<ProfileViewDetailedData>, String> {
+    let mut profiles = Vec::with_capacity(members.len());
+    for members in members.chunks(25) {
+        profiles.extend(
+            CLIENT
+                .service
+                .app
+                .bsky
+                .actor
+                .get_profiles(
+                    get_profiles::ParametersData {
+                        actors: members
+                            .iter()
+                            .map(|member| AtIdentifier::Did(member.did.clone()))
+                            .collect(),
+                    }
+                    .into(),
+                )
+                .await
+                .map_err(|_| OFFLINE.to_owned())?
+                .data
+                .profiles
+                .into_iter()
+                .map(|profile| profile.data),
+        )
+    }
+    Ok(profiles)
+}
+
+async fn did(actor: AtIdentifier) -> Result<atrium_api::types::string::Did, String> {
+    Ok(match actor {
+        AtIdentifier::Did(did) => did,
+        AtIdentifier::Handle(handle) => match CLIENT
+            .service
+            .com
+            .atproto
+            .identity
+            .resolve_handle(
+                resolve_handle::ParametersData {
+                    handle: handle.clone(),
+                }
+                .into(),
+            )
+            .await
+        {
+            Ok(output) => output.data.did,
+            // the appview might not have caught up with a new or custom domain yet
+            Err(atrium_xrpc::Error::XrpcResponse(_)) => {
+                auth::resolve(&handle).await.map_err(|_| {
+                    format!("couldn't find anyone with the handle @{}", handle.as_str())
+                })?
+            }
+            Err(_) => return Err(OFFLINE.into()),
+        },
+    })
+}
+
+async fn lookup(actor: AtIdentifier) -> Result<get_profile::Output, String> {
+    let did = did(actor).await?;
+    CLIENT
+        .service
+        .app
+        .bsky
+        .actor
+        .get_profile(
+            get_profile::ParametersData {
+                actor: AtIdentifier::Did(did),
+            }
+            .into(),
+        )
+        .await
+        .map_err(|e| match e {
+            atrium_xrpc::Error::XrpcResponse(atrium_xrpc::error::XrpcError {
+                error: Some(atrium_xrpc::error::XrpcErrorKind::Undefined(body)),
+                ..
+            }) => match body.error.as_deref() {
+                Some("AccountDeactivated") => "that account has been deactivated",
+                Some("AccountTakedown") => "that account has been suspended",
+                _ => "couldn't find that account",
+            }
+            .into(),
+            atrium_xrpc::Error::XrpcResponse(_) => "couldn't find that account".into(),
+            _ => OFFLINE.into(),
+        })
 }
 
 fn check(mut commands: Commands, mut ask: ResMut<Ask>, mut next: ResMut<NextState<Game>>) {
@@ -80,16 +560,103 @@ fn check(mut commands: Commands, mut ask: ResMut<Ask>, mut next: ResMut<NextStat
         .as_mut()
         .and_then(|task| bevy::tasks::block_on(bevy::tasks::futures_lite::future::poll_once(task)))
     {
-        Some(Ok(profile)) => {
-            commands.insert_resource(Profile {
-                actor: profile.handle.parse().unwrap(),
-                profile: profile.data,
-            });
+        Some(Ok((profiles, members))) => {
+            let mut roots = Roots::default();
+            let mut seen = std::collections::HashSet::new();
+            // lists aren't handles so there's nothing to remember
+            if members.is_empty()
+                && let Some(profile) = profiles.first()
+            {
+                remember(&mut ask.recent, profile.handle.to_string());
+            }
+            for profile in profiles {
+                // the same person twice is just one root
+                if !seen.insert(profile.did.clone()) {
+                    continue;
+                }
+                // crawled by did so a handle change mid crawl doesn't break it
+                roots.push(Profile {
+                    actor: AtIdentifier::Did(profile.did.clone()),
+                    profile,
+                });
+            }
+            commands.insert_resource(roots);
+            commands.insert_resource(Members(members));
+            ask.matches.clear();
             ask.buf.clear();
-            next.set(Game::Get)
+            ask.others.clear();
+            next.set(Game::Connect)
         }
-        Some(Err(e)) => ask.err = Some(e.to_string()),
+        Some(Err(e)) => ask.err = Some(e),
         None => return,
     }
     ask.task = None;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handles() {
+        for input in [
+            "spuds.casa",
+            " @spuds.casa ",
+            "Spuds.Casa",
+            "https://bsky.app/profile/spuds.casa",
+            "bsky.app/profile/@spuds.casa/post/3abc?ref=x",
+        ] {
+            assert_eq!(normalise(input), "spuds.casa".parse().ok(), "{input}");
+        }
+        // bare names are taken to be on bsky.social
+        assert_eq!(normalise("spuds"), "spuds.bsky.social".parse().ok());
+        assert_eq!(
+            normalise("did:plc:z72i7hdynmk6r22z27h6tvur"),
+            "did:plc:z72i7hdynmk6r22z27h6tvur".parse().ok()
+        );
+        assert_eq!(normalise(""), None);
+        assert_eq!(normalise("not a handle"), None);
+    }
+
+    #[test]
+    fn groups() {
+        let group = |input| match parse(input) {
+            Some(Who::Group(Group::Record(actor, collection, rkey))) => {
+                Some((actor, collection, rkey))
+            }
+            _ => None,
+        };
+        let actor: AtIdentifier = "spuds.casa".parse().unwrap();
+        assert_eq!(
+            group("https://bsky.app/profile/spuds.casa/lists/3abc"),
+            Some((actor.clone(), LIST, "3abc".into()))
+        );
+        assert_eq!(
+            group("at://spuds.casa/app.bsky.graph.list/3abc"),
+            Some((actor.clone(), LIST, "3abc".into()))
+        );
+        assert_eq!(
+            group("bsky.app/starter-pack/spuds.casa/3abc?ref=x"),
+            Some((actor.clone(), PACK, "3abc".into()))
+        );
+        assert_eq!(
+            group("https://bsky.app/start/spuds.casa/3abc"),
+            Some((actor.clone(), PACK, "3abc".into()))
+        );
+        assert_eq!(
+            group("at://spuds.casa/app.bsky.graph.starterpack/3abc"),
+            Some((actor, PACK, "3abc".into()))
+        );
+        // short links are followed later
+        assert!(matches!(
+            parse("go.bsky.app/AbCdEf"),
+            Some(Who::Group(Group::Short(link))) if link == "https://go.bsky.app/AbCdEf"
+        ));
+        // profile links are just the account
+        assert!(matches!(
+            parse("https://bsky.app/profile/spuds.casa"),
+            Some(Who::Actor(AtIdentifier::Handle(_)))
+        ));
+        assert!(parse("at://spuds.casa/app.bsky.feed.post/3abc").is_none());
+    }
+}
@@ -0,0 +1,630 @@
+use super::*;
+use atrium_api::agent::atp_agent::{AtpSession, CredentialSession};
+use atrium_api::types::string::Did;
+use atrium_common::resolver::Resolver;
+use atrium_common::store::Store;
+use atrium_xrpc::{HttpClient, OutputDataOrBytes, XrpcClient, XrpcRequest};
+use atrium_xrpc_client::reqwest::ReqwestClient;
+use std::sync::{Arc, RwLock};
+
+pub struct Stuff;
+
+impl Plugin for Stuff {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Login>()
+            .add_systems(Startup, restore)
+            .add_systems(
+                bevy_egui::EguiPrimaryContextPass,
+                login.run_if(in_state(Game::Ask)),
+            )
+            .add_systems(Update, check);
+    }
+}
+
+// whoever requests are being sent as
+enum Session {
+    Public(ReqwestClient),
+    Password(CredentialSession<Disk<(), AtpSession>, ReqwestClient>),
+    #[cfg(not(target_family = "wasm"))]
+    OAuth(OAuth),
+}
+
+#[cfg(not(target_family = "wasm"))]
+type OAuth = atrium_oauth::OAuthSession<
+    atrium_oauth::DefaultHttpClient,
+    Dids,
+    Handles,
+    Disk<Did, atrium_oauth::store::session::Session>,
+>;
+
+type Dids = atrium_identity::did::CommonDidResolver<ReqwestClient>;
+type Handles = atrium_identity::handle::AtprotoHandleResolver<
+    atrium_identity::handle::DohDnsTxtResolver<ReqwestClient>,
+    ReqwestClient,
+>;
+
+fn public() -> Session {
+    Session::Public(ReqwestClient::new("https://public.api.bsky.app"))
+}
+
+static SESSION: std::sync::LazyLock<RwLock<Arc<Session>>> =
+    std::sync::LazyLock::new(|| RwLock::new(Arc::new(public())));
+
+fn current() -> Arc<Session> {
+    SESSION.read().unwrap().clone()
+}
+
+fn swap(session: Session) {
+    *SESSION.write().unwrap() = Arc::new(session)
+}
+
+// sends requests as whoever's logged in so every call picks up the session
+pub struct Client;
+
+impl HttpClient for Client {
+    async fn send_http(
+        &self,
+        request: atrium_xrpc::http::Request<Vec<u8>>,
+    ) -> Result<
+        atrium_xrpc::http::Response<Vec<u8>>,
+        Box<dyn std::error::Error + Send + Sync + 'static>,
+    > {
+        match &*current() {
+            Session::Public(client) => client.send_http(request).await,
+            Session::Password(session) => session.send_http(request).await,
+            #[cfg(not(target_family = "wasm"))]
+            Session::OAuth(session) => session.send_http(request).await,
+        }
+    }
+}
+
+impl XrpcClient for Client {
+    fn base_uri(&self) -> String {
+        match &*current() {
+            Session::Public(client) => client.base_uri(),
+            Session::Password(session) => session.base_uri(),
+            #[cfg(not(target_family = "wasm"))]
+            Session::OAuth(session) => session.base_uri(),
+        }
+    }
+    async fn send_xrpc<P, I, O, E>(
+        &self,
+        request: &XrpcRequest<P, I>,
+    ) -> Result<OutputDataOrBytes<O>, atrium_xrpc::Error<E>>
+    where
+        P: serde::Serialize + Send + Sync,
+        I: serde::Serialize + Send + Sync,
+        O: serde::de::DeserializeOwned + Send + Sync,
+        E: serde::de::DeserializeOwned + Send + Sync + std::fmt::Debug,
+    {
+        // sessions refresh their own tokens when they expire
+        match &*current() {
+            Session::Public(client) => client.send_xrpc(request).await,
+            Session::Password(session) => session.send_xrpc(request).await,
+            #[cfg(not(target_family = "wasm"))]
+            Session::OAuth(session) => session.send_xrpc(request).await,
+        }
+    }
+}
+
+// keeps a single session in the os keychain so logins survive restarts
+// without one it falls back to a file in the config directory, which the login window owns up to
+// on wasm there's nowhere safe to put it so it only lasts as long as the tab
+struct Disk<K, V> {
+    name: &'static str,
+    value: std::sync::Mutex<Option<(K, V)>>,
+}
+
+// set once it turns out there's no keychain to use
+static PLAINTEXT: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+fn plaintext() -> bool {
+    PLAINTEXT.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn keychain(name: &str) -> Option<keyring::Entry> {
+    keyring::Entry::new("skyweb", name).ok()
+}
+
+impl<K: serde::de::DeserializeOwned, V: serde::de::DeserializeOwned> Disk<K, V> {
+    fn new(name: &'static str) -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        let bytes = match keychain(name).map(|entry| entry.get_secret()) {
+            Some(Ok(bytes)) => Some(bytes),
+            // the keychain works there's just nothing in it
+            Some(Err(keyring::Error::NoEntry)) => None,
+            _ => {
+                PLAINTEXT.store(true, std::sync::atomic::Ordering::Relaxed);
+                None
+            }
+        };
+        #[cfg(target_family = "wasm")]
+        let bytes = None;
+        // a login from before the keychain gets moved into it on the next save
+        let value = bytes
+            .or_else(|| path(name).and_then(|path| std::fs::read(path).ok()))
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+        Self {
+            name,
+            value: std::sync::Mutex::new(value),
+        }
+    }
+}
+
+impl<K: serde::Serialize, V: serde::Serialize> Disk<K, V> {
+    fn save(&self) -> std::io::Result<()> {
+        let bytes = self
+            .value
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(serde_json::to_vec)
+            .transpose()?;
+        #[cfg(not(target_family = "wasm"))]
+        if let Some(entry) = keychain(self.name) {
+            let saved = match &bytes {
+                Some(bytes) => entry.set_secret(bytes),
+                None => match entry.delete_credential() {
+                    Err(keyring::Error::NoEntry) => Ok(()),
+                    result => result,
+                },
+            };
+            match saved {
+                Ok(()) => return self.write(None),
+                Err(_) => PLAINTEXT.store(true, std::sync::atomic::Ordering::Relaxed),
+            }
+        }
+        self.write(bytes)
+    }
+
+    // the fallback for when there's no keychain
+    fn write(&self, bytes: Option<Vec<u8>>) -> std::io::Result<()> {
+        let Some(path) = path(self.name) else {
+            return Ok(());
+        };
+        let Some(bytes) = bytes else {
+            return match std::fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // tokens are as good as a password so only we get to read them
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        std::io::Write::write_all(&mut options.open(path)?, &bytes)
+    }
+}
+
+pub fn path(name: &str) -> Option<std::path::PathBuf> {
+    #[cfg(not(target_family = "wasm"))]
+    return dirs::config_dir().map(|dir| dir.join("skyweb").join(name));
+    #[cfg(target_family = "wasm")]
+    None
+}
+
+impl<K, V> Store<K, V> for Disk<K, V>
+where
+    K: Clone + Eq + std::hash::Hash + serde::Serialize + Send + Sync,
+    V: Clone + serde::Serialize + Send + Sync,
+{
+    type Error = std::io::Error;
+
+    async fn get(&self, key: &K) -> Result<Option<V>, Self::Error> {
+        Ok(self
+            .value
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.clone()))
+    }
+    async fn set(&self, key: K, value: V) -> Result<(), Self::Error> {
+        *self.value.lock().unwrap() = Some((key, value));
+        self.save()
+    }
+    async fn del(&self, key: &K) -> Result<(), Self::Error> {
+        {
+            let mut value = self.value.lock().unwrap();
+            if value.as_ref().is_some_and(|(k, _)| k == key) {
+                *value = None
+            }
+        }
+        self.save()
+    }
+    async fn clear(&self) -> Result<(), Self::Error> {
+        *self.value.lock().unwrap() = None;
+        self.save()
+    }
+}
+
+impl atrium_api::agent::AuthorizationProvider for Disk<(), AtpSession> {
+    async fn authorization_token(
+        &self,
+        is_refresh: bool,
+    ) -> Option<atrium_xrpc::types::AuthorizationToken> {
+        let session = self.get(&()).await.ok()??;
+        Some(atrium_xrpc::types::AuthorizationToken::Bearer(
+            match is_refresh {
+                true => session.refresh_jwt.clone(),
+                false => session.access_jwt.clone(),
+            },
+        ))
+    }
+}
+
+impl atrium_api::agent::atp_agent::store::AtpSessionStore for Disk<(), AtpSession> {}
+
+#[cfg(not(target_family = "wasm"))]
+impl atrium_oauth::store::session::SessionStore
+    for Disk<Did, atrium_oauth::store::session::Session>
+{
+}
+
+const PASSWORD: &str = "session.json";
+#[cfg(not(target_family = "wasm"))]
+const OAUTH: &str = "oauth.json";
+
+fn dids() -> Dids {
+    atrium_identity::did::CommonDidResolver::new(atrium_identity::did::CommonDidResolverConfig {
+        plc_directory_url: atrium_identity::did::DEFAULT_PLC_DIRECTORY_URL.into(),
+        http_client: Arc::new(ReqwestClient::new("")),
+    })
+}
+
+fn handles() -> Handles {
+    atrium_identity::handle::AtprotoHandleResolver::new(
+        atrium_identity::handle::AtprotoHandleResolverConfig {
+            dns_txt_resolver: atrium_identity::handle::DohDnsTxtResolver::new(
+                atrium_identity::handle::DohDnsTxtResolverConfig {
+                    service_url: "https://cloudflare-dns.com/dns-query".into(),
+                    http_client: Arc::new(ReqwestClient::new("")),
+                },
+            ),
+            http_client: Arc::new(ReqwestClient::new("")),
+        },
+    )
+}
+
+// dns first then https, for handles the appview doesn't know about
+pub async fn resolve(handle: &atrium_api::types::string::Handle) -> atrium_identity::Result<Did> {
+    handles().resolve(handle).await
+}
+
+// where someone's repo lives, for records the appview doesn't hand out
+pub async fn pds(did: &Did) -> Result<String, String> {
+    dids()
+        .resolve(did)
+        .await
+        .map_err(|e| e.to_string())?
+        .get_pds_endpoint()
+        .ok_or_else(|| "their did document doesn't say where their repo is".into())
+}
+
+// loopback clients have to redirect to 127.0.0.1 and the port can't change between runs
+// or the stored session won't be ours anymore
+#[cfg(not(target_family = "wasm"))]
+const PORT: u16 = 8736;
+
+#[cfg(not(target_family = "wasm"))]
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+#[cfg(not(target_family = "wasm"))]
+fn oauth() -> atrium_oauth::Result<
+    atrium_oauth::OAuthClient<
+        atrium_oauth::store::state::MemoryStateStore,
+        Disk<Did, atrium_oauth::store::session::Session>,
+        Dids,
+        Handles,
+    >,
+> {
+    atrium_oauth::OAuthClient::new(atrium_oauth::OAuthClientConfig {
+        client_metadata: atrium_oauth::AtprotoLocalhostClientMetadata {
+            redirect_uris: Some(vec![format!("http://127.0.0.1:{PORT}/callback")]),
+            scopes: Some(vec![
+                atrium_oauth::Scope::Known(atrium_oauth::KnownScope::Atproto),
+                atrium_oauth::Scope::Known(atrium_oauth::KnownScope::TransitionGeneric),
+            ]),
+        },
+        keys: None,
+        state_store: atrium_oauth::store::state::MemoryStateStore::default(),
+        session_store: Disk::new(OAUTH),
+        resolver: atrium_oauth::OAuthResolverConfig {
+            did_resolver: dids(),
+            handle_resolver: handles(),
+            authorization_server_metadata: default(),
+            protected_resource_metadata: default(),
+        },
+    })
+}
+
+// logs in with an app password against whichever pds hosts the account
+async fn password(identifier: String, password: String) -> Result<String, String> {
+    let identity = atrium_identity::identity_resolver::IdentityResolver::new(
+        atrium_identity::identity_resolver::IdentityResolverConfig {
+            did_resolver: dids(),
+            handle_resolver: handles(),
+        },
+    )
+    .resolve(identifier.trim().trim_start_matches('@'))
+    .await
+    .map_err(|e| e.to_string())?;
+    let session = CredentialSession::new(ReqwestClient::new(identity.pds), Disk::new(PASSWORD));
+    let output = session
+        .login(&identity.did, password)
+        .await
+        .map_err(|e| match e {
+            atrium_xrpc::Error::XrpcResponse(_) => "wrong handle or app password".into(),
+            e => e.to_string(),
+        })?;
+    // whoever was logged in before stays that way if this fails
+    clear(PASSWORD).await;
+    swap(Session::Password(session));
+    Ok(output.handle.to_string())
+}
+
+// opens the browser to log in then waits for it to redirect back to us
+#[cfg(not(target_family = "wasm"))]
+async fn authorize(handle: String) -> Result<String, String> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", PORT))
+        .await
+        .map_err(|e| e.to_string())?;
+    let client = oauth().map_err(|e| e.to_string())?;
+    let url = client
+        .authorize(
+            handle.trim().trim_start_matches('@'),
+            atrium_oauth::AuthorizeOptions {
+                scopes: vec![
+                    atrium_oauth::Scope::Known(atrium_oauth::KnownScope::Atproto),
+                    atrium_oauth::Scope::Known(atrium_oauth::KnownScope::TransitionGeneric),
+                ],
+                ..default()
+            },
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    webbrowser::open(&url).map_err(|e| e.to_string())?;
+    // don't hold the port forever if the tab gets closed
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    // the browser might ask for other things like a favicon first
+    let (mut stream, url) = loop {
+        let (stream, _) = tokio::time::timeout_at(deadline, listener.accept())
+            .await
+            .map_err(|_| "took too long to log in")?
+            .map_err(|e| e.to_string())?;
+        let mut stream = tokio::io::BufReader::new(stream);
+        let mut line = String::new();
+        if !matches!(
+            tokio::time::timeout_at(deadline, stream.read_line(&mut line)).await,
+            Ok(Ok(_))
+        ) {
+            continue;
+        }
+        // GET /callback?code=...&state=...&iss=... HTTP/1.1
+        let target = line.split_whitespace().nth(1).unwrap_or_default();
+        match reqwest::Url::parse(&format!("http://127.0.0.1{target}")) {
+            Ok(url) if url.path() == "/callback" => break (stream, url),
+            _ => {
+                let _ = stream
+                    .write_all(b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n")
+                    .await;
+            }
+        }
+    };
+    let query: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+    let params = query.get("code").map(|code| atrium_oauth::CallbackParams {
+        code: code.clone(),
+        state: query.get("state").cloned(),
+        iss: query.get("iss").cloned(),
+    });
+    let _ = stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\n\r\nyou can close this tab and head back to skyweb now",
+        )
+        .await;
+    let params = params.ok_or("the login was cancelled")?;
+    // this replaces whatever oauth session was stored before
+    let (session, _) = client.callback(params).await.map_err(|e| e.to_string())?;
+    clear(OAUTH).await;
+    swap(Session::OAuth(session));
+    whoami().await
+}
+
+async fn whoami() -> Result<String, String> {
+    CLIENT
+        .service
+        .com
+        .atproto
+        .server
+        .get_session()
+        .await
+        .map(|session| session.data.handle.to_string())
+        .map_err(|e| e.to_string())
+}
+
+// only one session is kept at a time so this drops the others
+async fn clear(keep: &str) {
+    if keep != PASSWORD {
+        let _ = Disk::<(), AtpSession>::new(PASSWORD).clear().await;
+    }
+    #[cfg(not(target_family = "wasm"))]
+    if keep != OAUTH {
+        let _ = Disk::<Did, atrium_oauth::store::session::Session>::new(OAUTH)
+            .clear()
+            .await;
+    }
+}
+
+// drops whatever session was stored before
+async fn forget() {
+    clear("").await;
+    swap(public())
+}
+
+// picks up where the last run left off
+async fn resume() -> Result<String, String> {
+    let store = Disk::<(), AtpSession>::new(PASSWORD);
+    if let Ok(Some(stored)) = store.get(&()).await {
+        let pds = stored
+            .did_doc
+            .clone()
+            .and_then(|doc| {
+                <atrium_api::did_doc::DidDocument as atrium_api::types::TryFromUnknown>::try_from_unknown(doc).ok()
+            })
+            .and_then(|doc| doc.get_pds_endpoint())
+            .unwrap_or("https://bsky.social".into());
+        let session = CredentialSession::new(ReqwestClient::new(pds), store);
+        session
+            .resume_session(stored.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+        swap(Session::Password(session));
+        return Ok(stored.handle.to_string());
+    }
+    #[cfg(not(target_family = "wasm"))]
+    if let Ok(Some((did, _))) = Disk::<Did, atrium_oauth::store::session::Session>::new(OAUTH)
+        .value
+        .into_inner()
+    {
+        // this refreshes the tokens if they've expired
+        let session = oauth()
+            .map_err(|e| e.to_string())?
+            .restore(&did)
+            .await
+            .map_err(|e| e.to_string())?;
+        swap(Session::OAuth(session));
+        return whoami().await;
+    }
+    Err(String::new())
+}
+
+#[derive(Resource, Default)]
+struct Login {
+    identifier: String,
+    password: String,
+    // who we're logged in as
+    who: Option<String>,
+    err: Option<String>,
+    task: Option<bevy::tasks::Task<Result<String, String>>>,
+}
+
+fn restore(mut login: ResMut<Login>) {
+    login.task = Some(bevy::tasks::IoTaskPool::get().spawn(Compat::new(resume())));
+}
+
+fn login(mut ctx: bevy_egui::EguiContexts, mut login: ResMut<Login>) {
+    use bevy_egui::egui;
+    let Ok(ctx) = ctx.ctx_mut() else { return };
+    let login = login.bypass_change_detection();
+    egui::Window::new("log in")
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -8.0])
+        .collapsible(true)
+        .default_open(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if login.task.is_some() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    // dropping the task stops it
+                    if ui.button("cancel").clicked() {
+                        login.task = None
+                    }
+                });
+                return;
+            }
+            if let Some(who) = &login.who {
+                ui.label(format!("logged in as {who}"));
+                if ui.button("log out").clicked() {
+                    login.who = None;
+                    login.task = Some(bevy::tasks::IoTaskPool::get().spawn(Compat::new(async {
+                        forget().await;
+                        Err(String::new())
+                    })));
+                }
+                return;
+            }
+            ui.label("logging in shows what only logged in users can see");
+            ui.add(egui::TextEdit::singleline(&mut login.identifier).hint_text("handle"));
+            ui.add(
+                egui::TextEdit::singleline(&mut login.password)
+                    .password(true)
+                    .hint_text("app password"),
+            );
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        !login.identifier.is_empty() && !login.password.is_empty(),
+                        egui::Button::new("log in"),
+                    )
+                    .clicked()
+                {
+                    let (identifier, pass) = (
+                        login.identifier.clone(),
+                        std::mem::take(&mut login.password),
+                    );
+                    login.task = Some(
+                        bevy::tasks::IoTaskPool::get()
+                            .spawn(Compat::new(password(identifier, pass))),
+                    );
+                }
+                #[cfg(not(target_family = "wasm"))]
+                if ui
+                    .add_enabled(
+                        !login.identifier.is_empty(),
+                        egui::Button::new("log in with browser"),
+                    )
+                    .clicked()
+                {
+                    login.task = Some(
+                        bevy::tasks::IoTaskPool::get()
+                            .spawn(Compat::new(authorize(login.identifier.clone()))),
+                    );
+                }
+            });
+            ui.hyperlink_to(
+                "make an app password",
+                "https://bsky.app/settings/app-passwords",
+            );
+            #[cfg(target_family = "wasm")]
+            ui.label("logging in with the browser only works in the desktop app");
+            // be upfront about it when there's no keychain
+            if let Some(dir) = path(PASSWORD)
+                .as_deref()
+                .and_then(std::path::Path::parent)
+                .filter(|_| plaintext())
+            {
+                ui.small(format!(
+                    "your login is saved unencrypted in {}",
+                    dir.display()
+                ));
+            }
+            if let Some(err) = &login.err {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+        });
+}
+
+fn check(mut login: ResMut<Login>) {
+    let Some(result) = login
+        .task
+        .as_mut()
+        .and_then(|task| bevy::tasks::block_on(bevy::tasks::poll_once(task)))
+    else {
+        return;
+    };
+    login.task = None;
+    match result {
+        Ok(who) => {
+            login.who = Some(who);
+            login.err = None
+        }
+        // an empty error is just nothing to resume
+        Err(err) => login.err = Some(err).filter(|err| !err.is_empty()),
+    }
+}
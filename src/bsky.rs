@@ -1,18 +1,18 @@
 use super::*;
 
+use atrium_api::app::bsky::actor::defs::ProfileViewData;
 use atrium_api::app::bsky::graph::get_follows;
 
 pub struct Stuff;
 
 impl Plugin for Stuff {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(Game::Get), spawn).add_systems(
-            Update,
-            (
-                get.run_if(in_state(Game::Get)),
-                connect.run_if(in_state(Game::Connect)),
-            ),
-        );
+        app.add_systems(OnEnter(Game::Connect), spawn.before(connect::setup))
+            .add_systems(
+                Update,
+                (get.run_if(any_with_component::<Root>), connect).run_if(in_state(Game::Connect)),
+            )
+            .add_observer(reset);
     }
 }
 
@@ -28,199 +28,359 @@ struct Follow {
     actor: atrium_api::types::string::AtIdentifier,
     cursor: Option<String>,
     task: bevy::tasks::Task<atrium_api::xrpc::Result<get_follows::Output, get_follows::Error>>,
+    // errors in a row
+    fails: usize,
 }
 
-#[derive(Resource, Deref, DerefMut)]
-struct You(Follow);
-
-fn spawn(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, profile: Res<Profile>) {
-    commands.insert_resource(Orb(meshes.add(Circle::new(6.0))));
-    commands.init_resource::<Network>();
-    let actor = profile.actor.clone();
-    commands.insert_resource(You(Follow {
-        actor: actor.clone(),
-        cursor: None,
-        task: bevy::tasks::IoTaskPool::get().spawn(Compat::new(
-            CLIENT.service.app.bsky.graph.get_follows(
-                get_follows::ParametersData {
-                    actor: actor.clone(),
-                    cursor: None,
-                    limit: limit(),
-                }
-                .into(),
+// how many errors in a row before a user's follows are given up on
+pub const RETRIES: usize = 5;
+const UNKNOWN: usize = 50_000;
+
+impl Follow {
+    fn new(actor: atrium_api::types::string::AtIdentifier) -> Self {
+        Self {
+            task: page(&actor, None),
+            actor,
+            cursor: None,
+            fails: 0,
+        }
+    }
+}
+
+fn page(
+    actor: &atrium_api::types::string::AtIdentifier,
+    cursor: Option<String>,
+) -> bevy::tasks::Task<atrium_api::xrpc::Result<get_follows::Output, get_follows::Error>> {
+    bevy::tasks::IoTaskPool::get().spawn(Compat::new(
+        CLIENT.service.app.bsky.graph.get_follows(
+            get_follows::ParametersData {
+                actor: actor.clone(),
+                cursor,
+                limit: limit(),
+            }
+            .into(),
+        ),
+    ))
+}
+
+// same spiral d3 starts nodes off in
+fn spiral(index: usize) -> Vec2 {
+    let angle = index as f32 * std::f32::consts::PI * (3.0 - 5.0f32.sqrt());
+    Vec2::from_angle(angle) * 10.0 * (0.5 + index as f32).sqrt()
+}
+
+// an orb with their avatar on it that's still fetching who they follow
+fn orb(
+    commands: &mut Commands,
+    handle: &Handle<Mesh>,
+    mats: &mut Assets<ColorMaterial>,
+    server: &AssetServer,
+    user: User,
+    follow: impl bevy::ecs::bundle::Bundle,
+    pos: Vec2,
+) -> Entity {
+    let avatar = server.load_with_settings(
+        user.avatar.clone().unwrap_or_default(),
+        |s: &mut bevy::image::ImageLoaderSettings| {
+            s.format = bevy::image::ImageFormatSetting::Guess
+        },
+    );
+    commands
+        .spawn((
+            Mesh2d(handle.clone_weak()),
+            user,
+            follow,
+            MeshMaterial2d(mats.add(ColorMaterial::from(avatar))),
+            Transform::from_translation(pos.extend(0.0)),
+        ))
+        .id()
+}
+
+// a root still paging through their own follows
+#[derive(Component, Deref, DerefMut)]
+struct Root(Follow);
+
+#[allow(clippy::too_many_arguments)]
+fn spawn(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut mats: ResMut<Assets<ColorMaterial>>,
+    server: Res<AssetServer>,
+    roots: Res<Roots>,
+    members: Res<Members>,
+    time: Res<Time>,
+) {
+    let handle = meshes.add(Circle::new(6.0));
+    // roots are there from the start so everyone else can be added as they come in
+    let mut network = Network::default();
+    for (index, profile) in roots.iter().enumerate() {
+        let user = User {
+            profile: ProfileViewData {
+                associated: profile.associated.clone(),
+                avatar: profile.avatar.clone(),
+                created_at: profile.created_at.clone(),
+                description: profile.description.clone(),
+                did: profile.did.clone(),
+                display_name: profile.display_name.clone(),
+                handle: profile.handle.clone(),
+                indexed_at: profile.indexed_at.clone(),
+                labels: profile.labels.clone(),
+                pronouns: profile.pronouns.clone(),
+                status: profile.status.clone(),
+                verification: profile.verification.clone(),
+                viewer: profile.viewer.clone(),
+            },
+            shared: Vec::new(),
+            fans: Vec::new(),
+            index,
+            followers: None,
+            community: None,
+        };
+        let follow = Root(Follow::new(profile.actor.clone()));
+        let pos = home(index, roots.len());
+        network.insert(
+            profile.did.clone(),
+            orb(
+                &mut commands,
+                &handle,
+                &mut mats,
+                &server,
+                user,
+                follow,
+                pos,
+            ),
+        );
+    }
+    // members are all there is so only the edges between them are crawled
+    let mut crawling = 0;
+    for profile in members.iter() {
+        if network.contains_key(&profile.did) {
+            continue;
+        }
+        let actor = atrium_api::types::string::AtIdentifier::Did(profile.did.clone());
+        let index = network.len();
+        let user = User {
+            profile: profile.clone(),
+            shared: Vec::new(),
+            fans: Vec::new(),
+            index,
+            followers: None,
+            community: None,
+        };
+        crawling += 1;
+        network.insert(
+            profile.did.clone(),
+            orb(
+                &mut commands,
+                &handle,
+                &mut mats,
+                &server,
+                user,
+                Follow::new(actor),
+                spiral(index),
             ),
-        )),
-    }));
+        );
+    }
+    commands.remove_resource::<Members>();
+    commands.insert_resource(network);
+    commands.insert_resource(Orb(handle));
+    commands.insert_resource(Progress {
+        fetching: !roots.is_empty(),
+        crawling,
+        since: time.elapsed_secs(),
+        ..default()
+    });
+    if !roots.is_empty() {
+        commands.insert_resource(Unknown::default());
+    }
+}
+
+fn reset(_: Trigger<Reset>, mut commands: Commands) {
+    commands.remove_resource::<Unknown>();
 }
 
+// follows of users that aren't in the web yet but might be on a later page of a root's
+// past UNKNOWN users anyone new isn't kept track of so their follows from before are missed
+#[derive(Resource, Deref, DerefMut, Default)]
+struct Unknown(std::collections::HashMap<atrium_api::types::string::Did, Vec<Entity>>);
+
+#[allow(clippy::too_many_arguments)]
 fn get(
     mut commands: Commands,
-    orb: Res<Orb>,
+    handle: Res<Orb>,
     server: Res<AssetServer>,
-    mut you: ResMut<You>,
+    mut roots: Query<(Entity, &User, &mut Root)>,
     mut network: ResMut<Network>,
+    mut progress: ResMut<Progress>,
+    mut unknown: ResMut<Unknown>,
     mut mats: ResMut<Assets<ColorMaterial>>,
-    mut next: ResMut<NextState<Game>>,
 ) {
-    let data = match bevy::tasks::block_on(bevy::tasks::poll_once(&mut you.task)) {
-        Some(Ok(atrium_api::types::Object { data, .. })) => {
-            let pool = bevy::tasks::IoTaskPool::get();
-            for follow in &data.follows {
-                // there used to be a bug in the app that allowed you to follow yourself
-                if follow.handle == data.subject.handle {
+    let mut left = roots.iter().count();
+    for (root, user, mut follow) in &mut roots {
+        match bevy::tasks::block_on(bevy::tasks::poll_once(&mut follow.task)) {
+            Some(Ok(atrium_api::types::Object { data, .. })) => {
+                progress.pages += 1;
+                progress.requests += 1;
+                progress.listed += data.follows.len();
+                let from = user.index;
+                for follow in &data.follows {
+                    // there used to be a bug in the app that allowed you to follow yourself
+                    if follow.did == data.subject.did {
+                        continue;
+                    }
+                    // another root got to them first
+                    if let Some(ent) = network.get(&follow.did).cloned() {
+                        commands.queue(move |world: &mut World| {
+                            world.get_mut::<User>(root).unwrap().shared.push(ent);
+                            let mut other = world.get_mut::<User>(ent).unwrap();
+                            other.fans.push(root);
+                            let to = other.index;
+                            world.resource_mut::<Sim>().follow(from, to);
+                        });
+                        continue;
+                    }
+                    let actor = atrium_api::types::string::AtIdentifier::Did(follow.did.clone());
+                    let index = network.len();
+                    let pos = spiral(index);
+                    let fans = unknown.remove(&follow.did).unwrap_or_default();
+                    progress.crawling += 1;
+                    let user = User {
+                        profile: follow.data.clone(),
+                        shared: Vec::new(),
+                        fans: std::iter::once(root).chain(fans.iter().cloned()).collect(),
+                        index,
+                        followers: None,
+                        community: None,
+                    };
+                    let ent = orb(
+                        &mut commands,
+                        &handle,
+                        &mut mats,
+                        &server,
+                        user,
+                        Follow::new(actor),
+                        pos,
+                    );
+                    network.insert(follow.did.clone(), ent);
+                    commands.queue(move |world: &mut World| {
+                        world.get_mut::<User>(root).unwrap().shared.push(ent);
+                        let mut links = vec![(from, index)];
+                        for fan in fans {
+                            let done = !world.entity(fan).contains::<Follow>();
+                            let mut fan = world.get_mut::<User>(fan).unwrap();
+                            fan.shared.push(ent);
+                            // otherwise it's linked up once they're done
+                            if done {
+                                links.push((fan.index, index))
+                            }
+                        }
+                        let mut sim = world.resource_mut::<Sim>();
+                        sim.add(pos);
+                        for (from, to) in links {
+                            sim.follow(from, to)
+                        }
+                    });
+                }
+                if data.cursor.is_none() {
+                    commands.entity(root).remove::<Root>();
+                    left -= 1;
                     continue;
                 }
-                let actor: atrium_api::types::string::AtIdentifier = follow.handle.parse().unwrap();
-                let index = network.len();
-                network.insert(
-                    follow.handle.as_str().into(),
-                    commands
-                        .spawn((
-                            Mesh2d(orb.clone_weak()),
-                            User {
-                                handle: follow.handle.to_string(),
-                                shared: Vec::new(),
-                                index,
-                            },
-                            Follow {
-                                actor: actor.clone(),
-                                cursor: None,
-                                task: pool.spawn(Compat::new(
-                                    CLIENT.service.app.bsky.graph.get_follows(
-                                        get_follows::ParametersData {
-                                            actor,
-                                            cursor: None,
-                                            limit: limit(),
-                                        }
-                                        .into(),
-                                    ),
-                                )),
-                            },
-                            MeshMaterial2d(mats.add(ColorMaterial::from(
-                                server.load_with_settings(
-                                    follow.avatar.clone().unwrap_or_default(),
-                                    |s: &mut bevy::image::ImageLoaderSettings| {
-                                        s.format = bevy::image::ImageFormatSetting::Guess
-                                    },
-                                ),
-                            ))),
-                            // Transform::from_translation(placement.next()),
-                        ))
-                        .id(),
-                );
+                follow.cursor = data.cursor;
+                follow.fails = 0;
             }
-            if data.cursor.is_some() {
-                you.cursor = data.cursor;
-                // duplicated code :/
-                you.task = bevy::tasks::IoTaskPool::get().spawn(Compat::new(
-                    CLIENT.service.app.bsky.graph.get_follows(
-                        get_follows::ParametersData {
-                            actor: you.actor.clone(),
-                            cursor: you.cursor.clone(),
-                            limit: limit(),
-                        }
-                        .into(),
-                    ),
-                ));
-                return;
+            Some(Err(_)) if follow.fails + 1 >= RETRIES => {
+                // whoever made it through still counts
+                progress.cut += 1;
+                commands.entity(root).remove::<Root>();
+                left -= 1;
+                continue;
             }
-            data
-        }
-        Some(Err(_)) => {
-            // duplicated code :/
-            you.task = bevy::tasks::IoTaskPool::get().spawn(Compat::new(
-                CLIENT.service.app.bsky.graph.get_follows(
-                    get_follows::ParametersData {
-                        actor: you.actor.clone(),
-                        cursor: you.cursor.clone(),
-                        limit: limit(),
-                    }
-                    .into(),
-                ),
-            ));
-            return;
+            Some(Err(_)) => follow.fails += 1,
+            None => continue,
         }
-        None => return,
-    };
-    let shared: Vec<_> = network.values().cloned().collect();
-    let index = network.len();
-    network.insert(
-        data.subject.handle.to_string(),
-        commands
-            .spawn((
-                User {
-                    handle: data.subject.handle.to_string(),
-                    shared,
-                    index,
-                },
-                Mesh2d(orb.clone_weak()),
-                MeshMaterial2d(mats.add(ColorMaterial::from(server.load_with_settings(
-                    data.subject.avatar.clone().unwrap_or_default(),
-                    |s: &mut bevy::image::ImageLoaderSettings| {
-                        s.format = bevy::image::ImageFormatSetting::Guess
-                    },
-                )))),
-            ))
-            .id(),
-    );
-    commands.remove_resource::<You>();
-    next.set(Game::Connect)
+        follow.task = page(&follow.actor, follow.cursor.clone());
+    }
+    if left == 0 {
+        progress.fetching = false;
+        commands.remove_resource::<Unknown>();
+    }
 }
 
 fn connect(
     mut commands: Commands,
     mut network: ResMut<Network>,
+    mut progress: ResMut<Progress>,
+    mut unknown: Option<ResMut<Unknown>>,
     mut users: Query<(Entity, &mut User, &mut Follow)>,
 ) {
     for (ent, mut user, mut follow) in &mut users {
         match bevy::tasks::block_on(bevy::tasks::poll_once(&mut follow.task)) {
             Some(Ok(atrium_api::types::Object { data, .. })) => {
+                progress.requests += 1;
+                follow.fails = 0;
+                let mut followed = Vec::new();
                 for follow in data.follows {
-                    if let Some(ent) = network.get(follow.handle.as_str()) {
-                        user.shared.push(*ent);
+                    match network.get(&follow.did) {
+                        Some(ent) => followed.push(*ent),
+                        None => {
+                            if let Some(unknown) = unknown.as_mut().filter(|unknown| {
+                                unknown.len() < UNKNOWN || unknown.contains_key(&follow.did)
+                            }) {
+                                unknown.entry(follow.did.clone()).or_default().push(ent)
+                            }
+                        }
                     }
                 }
+                user.shared.extend(&followed);
+                commands.queue(move |world: &mut World| {
+                    for other in followed {
+                        if let Some(mut other) = world.entity_mut(other).get_mut::<User>() {
+                            other.fans.push(ent)
+                        }
+                    }
+                });
                 if data.cursor.is_some() {
                     follow.cursor = data.cursor;
-                    // duplicated code :/
-                    follow.task = bevy::tasks::IoTaskPool::get().spawn(Compat::new(
-                        CLIENT.service.app.bsky.graph.get_follows(
-                            get_follows::ParametersData {
-                                actor: follow.actor.clone(),
-                                cursor: follow.cursor.clone(),
-                                limit: limit(),
-                            }
-                            .into(),
-                        ),
-                    ));
+                    follow.task = page(&follow.actor, follow.cursor.clone());
                     return;
                 }
-                network.max = user.shared.len().max(network.max);
-                commands.entity(ent).remove::<Follow>();
-                commands.queue(move |world: &mut World| {
-                    world.resource_scope(|world, mut sim: Mut<Sim>| {
-                        let user = world.entity(ent).get::<User>().unwrap();
-                        sim.links.extend(user.shared.iter().filter_map(|ent| {
-                            Some((user.index, world.entity(*ent).get::<User>().unwrap().index))
-                        }))
-                    });
-                    world.trigger(Rebuild);
-                });
+                progress.done += 1;
+                finish(&mut commands, &mut network, &mut progress, ent, &user);
+            }
+            Some(Err(_)) if follow.fails + 1 >= RETRIES => {
+                // whatever made it through still counts
+                progress.failed += 1;
+                finish(&mut commands, &mut network, &mut progress, ent, &user);
             }
             Some(Err(_)) => {
-                // duplicated code :/
-                follow.task = bevy::tasks::IoTaskPool::get().spawn(Compat::new(
-                    CLIENT.service.app.bsky.graph.get_follows(
-                        get_follows::ParametersData {
-                            actor: follow.actor.clone(),
-                            cursor: follow.cursor.clone(),
-                            limit: limit(),
-                        }
-                        .into(),
-                    ),
-                ));
+                follow.fails += 1;
+                follow.task = page(&follow.actor, follow.cursor.clone());
             }
             None => {}
         }
     }
 }
+
+fn finish(
+    commands: &mut Commands,
+    network: &mut Network,
+    progress: &mut Progress,
+    ent: Entity,
+    user: &User,
+) {
+    progress.crawling -= 1;
+    network.max = user.shared.len().max(network.max);
+    commands.entity(ent).remove::<Follow>();
+    commands.queue(move |world: &mut World| {
+        world.resource_scope(|world, mut sim: Mut<Sim>| {
+            let user = world.entity(ent).get::<User>().unwrap();
+            for other in &user.shared {
+                sim.follow(
+                    user.index,
+                    world.entity(*other).get::<User>().unwrap().index,
+                )
+            }
+        });
+    });
+}
@@ -0,0 +1,198 @@
+// force directed edge bundling from holten and van wijk
+// https://classes.engineering.wustl.edu/cse557/readings/holten-edgebundling.pdf
+// edges are split into points that attract the matching points of similar edges
+use bevy::math::Vec2;
+
+const CYCLES: usize = 5;
+const ITERATIONS: f32 = 60.0;
+const STEP: f32 = 0.1;
+const STIFFNESS: f32 = 0.1;
+const COMPATIBILITY: f32 = 0.6;
+// the most edges each one is attracted to so big webs still finish
+const NEIGHBOURS: usize = 16;
+
+// how alike two edges are in angle, length, position and whether they face each other
+fn compatibility((p0, p1): (Vec2, Vec2), (q0, q1): (Vec2, Vec2)) -> f32 {
+    let (p, q) = (p1 - p0, q1 - q0);
+    let (lp, lq) = (p.length(), q.length());
+    let avg = (lp + lq) / 2.0;
+    let angle = (p.dot(q) / (lp * lq)).abs();
+    let scale = 2.0 / (avg / lp.min(lq) + lp.max(lq) / avg);
+    let position = avg / (avg + p0.midpoint(p1).distance(q0.midpoint(q1)));
+    angle * scale * position * visibility((p0, p1), (q0, q1)).min(visibility((q0, q1), (p0, p1)))
+}
+
+fn visibility((p0, p1): (Vec2, Vec2), (q0, q1): (Vec2, Vec2)) -> f32 {
+    let project = |point: Vec2| {
+        let dir = p1 - p0;
+        p0 + dir * (point - p0).dot(dir) / dir.length_squared()
+    };
+    let (i0, i1) = (project(q0), project(q1));
+    let span = i0.distance(i1);
+    if span == 0.0 {
+        return 0.0;
+    }
+    (1.0 - 2.0 * p0.midpoint(p1).distance(i0.midpoint(i1)) / span).max(0.0)
+}
+
+// spreads the points back out evenly along the polyline
+fn subdivide(points: &[Vec2], count: usize) -> Vec<Vec2> {
+    let length: f32 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    let gap = length / (count + 1) as f32;
+    let mut out = Vec::with_capacity(count + 2);
+    out.push(points[0]);
+    let mut walked = 0.0;
+    let mut next = gap;
+    for w in points.windows(2) {
+        let seg = w[0].distance(w[1]);
+        while out.len() <= count && next <= walked + seg && seg > 0.0 {
+            out.push(w[0].lerp(w[1], (next - walked) / seg));
+            next += gap;
+        }
+        walked += seg;
+    }
+    while out.len() <= count {
+        out.push(points[points.len() - 1]);
+    }
+    out.push(points[points.len() - 1]);
+    out
+}
+
+pub fn bundle(ends: Vec<(Vec2, Vec2)>) -> Vec<Vec<Vec2>> {
+    let valid: Vec<bool> = ends.iter().map(|(a, b)| a.distance(*b) > 0.0).collect();
+    // only edges with nearby midpoints can be compatible so bucket them on a grid
+    let cell = {
+        let mut lengths: Vec<f32> = ends.iter().map(|(a, b)| a.distance(*b)).collect();
+        lengths.sort_unstable_by(f32::total_cmp);
+        lengths
+            .get(lengths.len() / 2)
+            .copied()
+            .unwrap_or(1.0)
+            .max(1.0)
+    };
+    let key = |point: Vec2| {
+        (
+            (point.x / cell).floor() as i32,
+            (point.y / cell).floor() as i32,
+        )
+    };
+    let mut grid: std::collections::HashMap<(i32, i32), Vec<usize>> = Default::default();
+    for (i, (a, b)) in ends.iter().enumerate() {
+        if valid[i] {
+            grid.entry(key(a.midpoint(*b))).or_default().push(i);
+        }
+    }
+    // whether the other edge runs the opposite way is kept to pair up the right points
+    let compatible: Vec<Vec<(usize, bool)>> = ends
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            if !valid[i] {
+                return Vec::new();
+            }
+            let (x, y) = key(p.0.midpoint(p.1));
+            let mut near: Vec<_> = (x - 1..=x + 1)
+                .flat_map(|x| (y - 1..=y + 1).map(move |y| (x, y)))
+                .filter_map(|cell| grid.get(&cell))
+                .flatten()
+                .filter(|j| **j != i)
+                .map(|j| (compatibility(*p, ends[*j]), *j))
+                .filter(|(c, _)| *c >= COMPATIBILITY)
+                .collect();
+            near.sort_unstable_by(|(a, _), (b, _)| b.total_cmp(a));
+            near.truncate(NEIGHBOURS);
+            near.into_iter()
+                .map(|(_, j)| (j, (p.1 - p.0).dot(ends[j].1 - ends[j].0) < 0.0))
+                .collect()
+        })
+        .collect();
+    let mut lines: Vec<Vec<Vec2>> = ends.iter().map(|(a, b)| vec![*a, *b]).collect();
+    let mut count = 1;
+    let mut step = STEP;
+    let mut iterations = ITERATIONS;
+    for _ in 0..CYCLES {
+        lines = lines.iter().map(|line| subdivide(line, count)).collect();
+        for _ in 0..iterations as usize {
+            let moved: Vec<Vec<Vec2>> = lines
+                .iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    if !valid[i] {
+                        return line.clone();
+                    }
+                    let spring = STIFFNESS / (ends[i].0.distance(ends[i].1) * (count + 1) as f32);
+                    let mut out = line.clone();
+                    for k in 1..=count {
+                        let point = line[k];
+                        let mut force = (line[k - 1] - point + line[k + 1] - point) * spring;
+                        for (j, flip) in &compatible[i] {
+                            let other = lines[*j][if *flip { count + 1 - k } else { k }];
+                            let diff = other - point;
+                            let dist = diff.length();
+                            if dist > 1e-4 {
+                                force += diff / dist;
+                            }
+                        }
+                        out[k] = point + force * step;
+                    }
+                    out
+                })
+                .collect();
+            lines = moved;
+        }
+        count *= 2;
+        step /= 2.0;
+        iterations *= 2.0 / 3.0;
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the same either way round since there's an even number of points
+    fn middle(line: &[Vec2]) -> Vec2 {
+        line[line.len() / 2 - 1].midpoint(line[line.len() / 2])
+    }
+
+    #[test]
+    fn ends() {
+        let ends = vec![
+            (Vec2::ZERO, Vec2::new(100.0, 0.0)),
+            (Vec2::new(0.0, 10.0), Vec2::new(100.0, 10.0)),
+            (Vec2::new(5.0, 5.0), Vec2::new(5.0, 5.0)),
+        ];
+        let lines = bundle(ends.clone());
+        assert_eq!(lines.len(), ends.len());
+        for (line, (a, b)) in lines.iter().zip(ends) {
+            assert_eq!(line.len(), lines[0].len());
+            assert_eq!((line[0], line[line.len() - 1]), (a, b));
+            assert!(line.iter().all(|point| point.is_finite()));
+        }
+    }
+
+    #[test]
+    fn parallel() {
+        // close edges running the same way pull together and so do ones running opposite ways
+        for flip in [false, true] {
+            let (a, b) = (Vec2::new(0.0, 10.0), Vec2::new(100.0, 10.0));
+            let lines = bundle(vec![
+                (Vec2::ZERO, Vec2::new(100.0, 0.0)),
+                if flip { (b, a) } else { (a, b) },
+            ]);
+            assert!(middle(&lines[0]).distance(middle(&lines[1])) < 1.0);
+        }
+    }
+
+    #[test]
+    fn crossing() {
+        // edges at right angles have nothing in common so stay straight
+        let lines = bundle(vec![
+            (Vec2::new(-50.0, 0.0), Vec2::new(50.0, 0.0)),
+            (Vec2::new(0.0, -50.0), Vec2::new(0.0, 50.0)),
+        ]);
+        assert!(lines[0].iter().all(|point| point.y.abs() < 1e-3));
+        assert!(lines[1].iter().all(|point| point.x.abs() < 1e-3));
+    }
+}
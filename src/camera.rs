@@ -4,26 +4,195 @@ pub struct Stuff;
 
 impl Plugin for Stuff {
     fn build(&self, app: &mut App) {
-        app.add_systems(
+        app.insert_resource(Zoom {
+            scale: 0.5,
+            anchor: None,
+        })
+        .init_resource::<Glide>()
+        .init_resource::<Bookmarks>()
+        .add_observer(fit)
+        .add_observer(focus)
+        .add_systems(
             Update,
-            pan.run_if(bevy::input::common_conditions::input_pressed(
-                MouseButton::Right,
-            ))
-            .run_if(in_state(Game::Connect)),
+            (
+                pan.run_if(bevy::input::common_conditions::input_pressed(
+                    MouseButton::Right,
+                )),
+                (scroll, touch, ease).chain(),
+            )
+                .run_if(in_state(Game::Connect)),
         );
     }
 }
 
 fn pan(
     mut mouse: EventReader<bevy::input::mouse::MouseMotion>,
+    mut glide: ResMut<Glide>,
     proj: Single<&Projection>,
     mut trans: Single<&mut Transform, With<Camera2d>>,
 ) {
     let Projection::Orthographic(proj) = &**proj else {
         return;
     };
+    **glide = None;
     for motion in mouse.read() {
         trans.translation.x -= motion.delta.x * proj.scale;
         trans.translation.y += motion.delta.y * proj.scale;
     }
 }
+
+fn scroll(
+    mut wheel: EventReader<bevy::input::mouse::MouseWheel>,
+    mut pinch: EventReader<bevy::input::gestures::PinchGesture>,
+    mut ctx: bevy_egui::EguiContexts,
+    mut zoom: ResMut<Zoom>,
+    window: Single<&Window, With<bevy::window::PrimaryWindow>>,
+) {
+    // let egui windows scroll themselves
+    if ctx.ctx_mut().is_ok_and(|ctx| ctx.is_pointer_over_area()) {
+        wheel.clear();
+        pinch.clear();
+        return;
+    }
+    let mut factor = 1.0;
+    for wheel in wheel.read() {
+        let lines = match wheel.unit {
+            bevy::input::mouse::MouseScrollUnit::Line => wheel.y,
+            // roughly how many pixels browsers scroll a line by
+            bevy::input::mouse::MouseScrollUnit::Pixel => wheel.y / 100.0,
+        };
+        factor *= 1.2f32.powf(-lines);
+    }
+    for pinch in pinch.read() {
+        factor /= 1.0 + pinch.0;
+    }
+    if factor == 1.0 {
+        return;
+    }
+    zoom.scale = (zoom.scale * factor).max(0.1);
+    zoom.anchor = window.cursor_position();
+}
+
+fn touch(
+    touches: Res<Touches>,
+    mut ctx: bevy_egui::EguiContexts,
+    mut zoom: ResMut<Zoom>,
+    mut glide: ResMut<Glide>,
+    grabbed: Query<(), With<Grab>>,
+    proj: Single<&Projection>,
+    mut trans: Single<&mut Transform, With<Camera2d>>,
+) {
+    let Projection::Orthographic(proj) = &**proj else {
+        return;
+    };
+    if ctx.ctx_mut().is_ok_and(|ctx| ctx.wants_pointer_input()) {
+        return;
+    }
+    let mut touches = touches.iter();
+    match (touches.next(), touches.next()) {
+        // dragging an orb shouldn't drag the camera along with it
+        (Some(touch), None) if grabbed.is_empty() => {
+            **glide = None;
+            let delta = touch.delta();
+            trans.translation.x -= delta.x * proj.scale;
+            trans.translation.y += delta.y * proj.scale;
+        }
+        (Some(first), Some(second)) => {
+            **glide = None;
+            let before = first
+                .previous_position()
+                .distance(second.previous_position());
+            let after = first.position().distance(second.position());
+            let mid = first.position().midpoint(second.position());
+            let delta = mid
+                - first
+                    .previous_position()
+                    .midpoint(second.previous_position());
+            trans.translation.x -= delta.x * proj.scale;
+            trans.translation.y += delta.y * proj.scale;
+            if before > 0.0 && after > 0.0 {
+                zoom.scale = (zoom.scale * before / after).max(0.1);
+                zoom.anchor = Some(mid);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn ease(
+    time: Res<Time>,
+    zoom: Res<Zoom>,
+    mut glide: ResMut<Glide>,
+    window: Single<&Window, With<bevy::window::PrimaryWindow>>,
+    mut proj: Single<&mut Projection>,
+    mut trans: Single<&mut Transform, With<Camera2d>>,
+) {
+    let Projection::Orthographic(proj) = &mut **proj else {
+        return;
+    };
+    let t = 1.0 - (-time.delta_secs() * 12.0).exp();
+    if let Some(target) = **glide {
+        let pos = trans.translation.truncate();
+        let pos = match pos.distance(target) < 0.01 {
+            true => {
+                **glide = None;
+                target
+            }
+            false => pos.lerp(target, t),
+        };
+        trans.translation.x = pos.x;
+        trans.translation.y = pos.y;
+    }
+    if proj.scale == zoom.scale {
+        return;
+    }
+    let old = proj.scale;
+    proj.scale = match (zoom.scale - old).abs() < 0.0001 {
+        true => zoom.scale,
+        false => old + (zoom.scale - old) * t,
+    };
+    // keep whatever's under the anchor in the same place on screen
+    let Some(anchor) = zoom.anchor.filter(|_| glide.is_none()) else {
+        return;
+    };
+    let offset = anchor - window.size() / 2.0;
+    trans.translation.x += offset.x * (old - proj.scale);
+    trans.translation.y -= offset.y * (old - proj.scale);
+}
+
+fn fit(
+    _: Trigger<Fit>,
+    mut zoom: ResMut<Zoom>,
+    mut glide: ResMut<Glide>,
+    config: Res<Config>,
+    window: Single<&Window, With<bevy::window::PrimaryWindow>>,
+    users: Query<(&Transform, &InheritedVisibility), With<User>>,
+) {
+    let Some(rect) = users
+        .iter()
+        .filter(|(_, vis)| vis.get())
+        .map(|(trans, _)| Rect::from_center_size(trans.translation.truncate(), Vec2::ZERO))
+        .reduce(|acc, rect| acc.union(rect))
+    else {
+        return;
+    };
+    let rect = rect.inflate(config.size * 2.0);
+    let size = rect.size() / window.size();
+    **glide = Some(rect.center());
+    zoom.scale = size.x.max(size.y).max(0.1);
+    zoom.anchor = None;
+}
+
+fn focus(
+    trigger: Trigger<Focus>,
+    mut zoom: ResMut<Zoom>,
+    mut glide: ResMut<Glide>,
+    users: Query<&Transform, With<User>>,
+) {
+    let Ok(trans) = users.get(trigger.target()) else {
+        return;
+    };
+    **glide = Some(trans.translation.truncate());
+    zoom.scale = zoom.scale.min(0.5);
+    zoom.anchor = None;
+}
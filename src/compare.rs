@@ -0,0 +1,196 @@
+use super::*;
+
+pub struct Stuff;
+
+impl Plugin for Stuff {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, tint.run_if(in_state(Game::Connect).and(comparing)))
+            .add_systems(
+                bevy_egui::EguiPrimaryContextPass,
+                summary.run_if(in_state(Game::Connect).and(comparing)),
+            );
+    }
+}
+
+const A: Color = Color::srgb(1.0, 0.55, 0.45);
+const B: Color = Color::srgb(0.45, 0.7, 1.0);
+const BOTH: Color = Color::srgb(0.8, 0.55, 1.0);
+
+fn comparing(roots: Option<Res<Roots>>) -> bool {
+    roots.is_some_and(|roots| roots.len() == 2)
+}
+
+// the two users being compared if that's what's happening
+pub fn pair(roots: &Roots, network: &Network) -> Option<(Entity, Entity)> {
+    match &roots[..] {
+        [a, b] => Some((*network.get(&a.did)?, *network.get(&b.did)?)),
+        _ => None,
+    }
+}
+
+// which of the two follow them
+pub fn side(user: &User, (a, b): (Entity, Entity)) -> Option<Color> {
+    colour(user.fans.contains(&a), user.fans.contains(&b))
+}
+
+// which of the two follow either end of a link
+pub fn span(ua: &User, ub: &User, (a, b): (Entity, Entity)) -> Option<Color> {
+    let by = |root| ua.fans.contains(&root) || ub.fans.contains(&root);
+    colour(by(a), by(b))
+}
+
+fn colour(a: bool, b: bool) -> Option<Color> {
+    match (a, b) {
+        (true, true) => Some(BOTH),
+        (true, false) => Some(A),
+        (false, true) => Some(B),
+        (false, false) => None,
+    }
+}
+
+// who only a follows, who only b follows and who they both follow
+fn overlap(ua: &User, ub: &User, (a, b): (Entity, Entity)) -> (usize, usize, usize) {
+    // following each other isn't really a follow in common
+    let fa: std::collections::HashSet<_> = ua.shared.iter().filter(|ent| **ent != b).collect();
+    let fb: std::collections::HashSet<_> = ub.shared.iter().filter(|ent| **ent != a).collect();
+    let both = fa.intersection(&fb).count();
+    (fa.len() - both, fb.len() - both, both)
+}
+
+// the share of everyone either follows that both do
+fn jaccard((only_a, only_b, both): (usize, usize, usize)) -> f32 {
+    both as f32 / (only_a + only_b + both).max(1) as f32
+}
+
+fn tint(
+    roots: Res<Roots>,
+    network: Res<Network>,
+    mut mats: ResMut<Assets<ColorMaterial>>,
+    users: Query<(&User, &MeshMaterial2d<ColorMaterial>), Changed<User>>,
+) {
+    let Some(pair) = pair(&roots, &network) else {
+        return;
+    };
+    for (user, mat) in &users {
+        let Some(mat) = mats.get_mut(&mat.0) else {
+            continue;
+        };
+        // keep whatever search has faded it to
+        let alpha = mat.color.alpha();
+        mat.color = side(user, pair).unwrap_or(Color::WHITE).with_alpha(alpha);
+    }
+}
+
+fn summary(
+    mut ctx: bevy_egui::EguiContexts,
+    roots: Res<Roots>,
+    network: Res<Network>,
+    users: Query<&User>,
+) {
+    use bevy_egui::egui;
+    let Some((a, b)) = pair(&roots, &network) else {
+        return;
+    };
+    let (Ok(ua), Ok(ub)) = (users.get(a), users.get(b)) else {
+        return;
+    };
+    let Ok(ctx) = ctx.ctx_mut() else { return };
+    let (only_a, only_b, both) = overlap(ua, ub, (a, b));
+    let colour = |colour: Color| {
+        let [r, g, b, _] = colour.to_srgba().to_u8_array();
+        egui::Color32::from_rgb(r, g, b)
+    };
+    let (na, nb) = (ua.handle.as_str(), ub.handle.as_str());
+    egui::Window::new("compare").show(ctx, |ui| {
+        ui.colored_label(colour(A), format!("only followed by @{na}: {only_a}"));
+        ui.colored_label(colour(B), format!("only followed by @{nb}: {only_b}"));
+        ui.colored_label(colour(BOTH), format!("followed by both: {both}"));
+        ui.label(format!(
+            "{:.1}% overlap",
+            jaccard((only_a, only_b, both)) * 100.0
+        ));
+        ui.label(match (ua.shared.contains(&b), ub.shared.contains(&a)) {
+            (true, true) => "they follow each other".to_owned(),
+            (true, false) => format!("@{na} follows @{nb}"),
+            (false, true) => format!("@{nb} follows @{na}"),
+            (false, false) => "neither follows the other".to_owned(),
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(index: usize) -> Profile {
+        Profile {
+            actor: format!("dummy{index}.bsky.social").parse().unwrap(),
+            profile: serde_json::from_value(serde_json::json!({
+                "did": format!("did:plc:dummy{index}"),
+                "handle": format!("dummy{index}.bsky.social"),
+            }))
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn pairs() {
+        let mut world = World::new();
+        let ents: Vec<_> = (0..3).map(|i| world.spawn(dummy(i)).id()).collect();
+        let mut network = Network::default();
+        for (i, ent) in ents.iter().enumerate() {
+            network.insert(format!("did:plc:dummy{i}").parse().unwrap(), *ent);
+        }
+        let roots = |indices: &[usize]| Roots(indices.iter().map(|i| root(*i)).collect());
+        assert_eq!(pair(&roots(&[0, 1]), &network), Some((ents[0], ents[1])));
+        // only ever two at a time
+        assert_eq!(pair(&roots(&[0]), &network), None);
+        assert_eq!(pair(&roots(&[0, 1, 2]), &network), None);
+        // not in the web yet
+        assert_eq!(pair(&roots(&[0, 3]), &network), None);
+    }
+
+    #[test]
+    fn sides() {
+        let mut world = World::new();
+        let (a, b, c) = (
+            world.spawn_empty().id(),
+            world.spawn_empty().id(),
+            world.spawn_empty().id(),
+        );
+        let mut user = dummy(0);
+        assert_eq!(side(&user, (a, b)), None);
+        user.fans = vec![c, a];
+        assert_eq!(side(&user, (a, b)), Some(A));
+        user.fans = vec![b];
+        assert_eq!(side(&user, (a, b)), Some(B));
+        user.fans = vec![b, c, a];
+        assert_eq!(side(&user, (a, b)), Some(BOTH));
+        // either end being followed counts for a link
+        let mut other = dummy(1);
+        user.fans = vec![a];
+        assert_eq!(span(&user, &other, (a, b)), Some(A));
+        other.fans = vec![b];
+        assert_eq!(span(&user, &other, (a, b)), Some(BOTH));
+        user.fans.clear();
+        assert_eq!(span(&user, &other, (a, b)), Some(B));
+        other.fans.clear();
+        assert_eq!(span(&user, &other, (a, b)), None);
+    }
+
+    #[test]
+    fn overlaps() {
+        let mut world = World::new();
+        let ents: Vec<_> = (0..6).map(|_| world.spawn_empty().id()).collect();
+        let (a, b) = (ents[0], ents[1]);
+        let (mut ua, mut ub) = (dummy(0), dummy(1));
+        // following each other doesn't count
+        ua.shared = vec![b, ents[2], ents[3], ents[4]];
+        ub.shared = vec![a, ents[3], ents[4], ents[5]];
+        let counts = overlap(&ua, &ub, (a, b));
+        assert_eq!(counts, (1, 1, 2));
+        assert_eq!(jaccard(counts), 0.5);
+        // nobody followed at all isn't a division by zero
+        assert_eq!(jaccard(overlap(&dummy(0), &dummy(1), (a, b))), 0.0);
+    }
+}
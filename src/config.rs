@@ -7,25 +7,32 @@ impl Plugin for Stuff {
         app.add_systems(
             bevy_egui::EguiPrimaryContextPass,
             config.run_if(in_state(Game::Connect)),
-        );
+        )
+        .add_observer(reset);
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn config(
     mut ctx: bevy_egui::EguiContexts,
     mut commands: Commands,
     mut config: ResMut<Config>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut next: ResMut<NextState<Game>>,
     orb: Res<Orb>,
-    users: Query<Entity, With<User>>,
-    mut proj: Single<&mut Projection>,
+    users: Query<(Entity, &User)>,
+    mut search: ResMut<Search>,
+    pinned: Query<Entity, With<Pinned>>,
+    mut zoom: ResMut<Zoom>,
+    mut glide: ResMut<Glide>,
+    mut bookmarks: ResMut<Bookmarks>,
+    roots: Res<Roots>,
+    network: Res<Network>,
+    camera: Single<&Transform, With<Camera2d>>,
+    (mut filter, hidden, communities): (ResMut<Filter>, Res<Hidden>, Res<Communities>),
+    mut sim: ResMut<Sim>,
 ) {
     use bevy_egui::egui;
     let Ok(ctx) = ctx.ctx_mut() else { return };
-    let Projection::Orthographic(proj) = &mut **proj else {
-        return;
-    };
     #[rustfmt::skip]
     // on wasm this is shown on the webpage
     #[cfg(not(target_family = "wasm"))]
@@ -57,8 +64,8 @@ fn config(
         });
         ui.horizontal_wrapped(|ui| {
             ui.label("•");
-            ui.hyperlink_to("fjadra", "https://crates.io/crates/fjadra");
-            ui.label("which implements the verlet physics for laying out the orbs")
+            ui.hyperlink_to("d3-force", "https://d3js.org/d3-force");
+            ui.label("which the physics for laying out the orbs is modelled on")
         });
         ui.horizontal_wrapped(|ui| {
             ui.label("•");
@@ -66,11 +73,18 @@ fn config(
             ui.label("which was able to fill in for bevy while its widgets are still cooking")
         });
     });
-    let mut rebuild = false;
     #[rustfmt::skip]
     egui::Window::new("config").show(ctx, |ui| {
         ui.label("to pan the camera");
         ui.label("right-click + drag");
+        ui.label("to zoom the camera");
+        ui.label("scroll or pinch");
+        ui.label("to see someone's profile");
+        ui.label("left-click");
+        ui.label("to move an orb");
+        ui.label("left-click + drag");
+        ui.label("to unpin an orb");
+        ui.label("middle-click");
         ui.horizontal(|ui| {
             ui.label("paused:");
             ui.checkbox(&mut config.paused, egui::Atoms::default());
@@ -81,36 +95,209 @@ fn config(
         });
         ui.horizontal(|ui| {
             ui.label("charge:");
-            rebuild |= ui.add(egui::DragValue::new(&mut config.charge).range(f32::MIN..=0.0)).changed();
+            if ui.add(egui::DragValue::new(&mut config.charge).range(f32::MIN..=0.0)).changed() {
+                sim.change(layout::Change::Charge(config.charge))
+            }
         });
         ui.horizontal(|ui| {
             ui.label("link:");
-            rebuild |= ui.add(egui::DragValue::new(&mut config.link).range(0.0..=f32::MAX)).changed();
+            if ui.add(egui::DragValue::new(&mut config.link).range(0.0..=f32::MAX)).changed() {
+                sim.change(layout::Change::Distance(config.link))
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("directed edges:");
+            ui.checkbox(&mut config.directed, egui::Atoms::default());
         });
-        if rebuild {
-            commands.trigger(Rebuild)
+        ui.horizontal(|ui| {
+            ui.label("bundle edges:");
+            ui.checkbox(&mut config.bundle, egui::Atoms::default());
+            ui.add_enabled(config.bundle, egui::Slider::new(&mut config.bundling, 0.0..=1.0));
+        });
+        if roots.len() > 1 {
+            ui.horizontal(|ui| {
+                ui.label("anchor roots:");
+                if ui.checkbox(&mut config.anchored, egui::Atoms::default()).changed() {
+                    // anyone pinned by hand stays where they were put
+                    for (i, ent) in roots.ents(&network).enumerate() {
+                        if !pinned.contains(ent) {
+                            sim.rest(i, roots.len(), config.anchored)
+                        }
+                    }
+                }
+            });
         }
+        ui.horizontal(|ui| {
+            ui.label("labels:");
+            ui.add(egui::DragValue::new(&mut config.labelled).range(0..=usize::MAX));
+            egui::ComboBox::from_id_salt("rank")
+                .selected_text(match config.rank {
+                    Rank::Followed => "most followed",
+                    Rank::Follows => "most follows",
+                    Rank::Degree => "most connected",
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut config.rank, Rank::Followed, "most followed");
+                    ui.selectable_value(&mut config.rank, Rank::Follows, "most follows");
+                    ui.selectable_value(&mut config.rank, Rank::Degree, "most connected");
+                });
+        });
         ui.horizontal(|ui| {
             ui.label("size:");
-            if ui.add(egui::DragValue::new(&mut config.size).range(0.0..=f32::MAX)).changed() {
-                if let Some(orb) = meshes.get_mut(&**orb) {
-                    *orb = Mesh::from(Circle::new(config.size))
-                }
+            if ui.add(egui::DragValue::new(&mut config.size).range(0.0..=f32::MAX)).changed()
+                && let Some(orb) = meshes.get_mut(&**orb)
+            {
+                *orb = Mesh::from(Circle::new(config.size))
             }
         });
+        ui.horizontal(|ui| {
+            ui.label("pin after drag:");
+            ui.checkbox(&mut config.pin, egui::Atoms::default());
+        });
+        if ui.button("unpin all").clicked() {
+            for ent in &pinned {
+                commands.trigger_targets(Unpin, ent)
+            }
+        }
         ui.horizontal(|ui| {
             ui.label("zoom:");
-            ui.add(egui::DragValue::new(&mut proj.scale).range(0.1..=f32::MAX).speed(0.02));
+            if ui.add(egui::DragValue::new(&mut zoom.scale).range(0.1..=f32::MAX).speed(0.02)).changed() {
+                zoom.anchor = None
+            }
         });
-        if ui.button("reset").clicked() {
-            commands.remove_resource::<Sim>();
-            commands.remove_resource::<Profile>();
-            commands.remove_resource::<Network>();
-            commands.remove_resource::<Lines>();
-            for ent in &users {
-                commands.entity(ent).despawn()
+        ui.horizontal(|ui| {
+            if ui.button("fit all").clicked() {
+                commands.trigger(Fit)
+            }
+            if ui.button("focus you").clicked()
+                && let Some(you) = roots.ents(&network).next()
+            {
+                commands.trigger_targets(Focus, you)
+            }
+            if ui.button("save view").clicked() {
+                bookmarks.push((camera.translation.truncate(), zoom.scale))
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("search:");
+            // only rerun the search when the query actually changes
+            let res = ui.text_edit_singleline(&mut search.bypass_change_detection().query);
+            if res.changed() {
+                search.set_changed()
+            }
+            if res.lost_focus()
+                && ui.input(|input| input.key_pressed(egui::Key::Enter))
+                && let Some(ent) = search.matches.first()
+            {
+                commands.trigger_targets(Focus, *ent)
+            }
+        });
+        for ent in search.matches.iter().take(8) {
+            let Ok((_, user)) = users.get(*ent) else {
+                continue;
+            };
+            let text = match user.name().is_empty() {
+                true => user.handle.to_string(),
+                false => format!("{} @{}", user.name(), user.handle.as_str()),
+            };
+            if ui.button(text).clicked() {
+                commands.trigger_targets(Focus, *ent)
             }
-            next.set(Game::Ask)
+        }
+        let mut remove = None;
+        for (i, (pos, scale)) in bookmarks.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button(format!("view {}", i + 1)).clicked() {
+                    **glide = Some(*pos);
+                    zoom.scale = *scale;
+                    zoom.anchor = None;
+                }
+                if ui.button("x").clicked() {
+                    remove = Some(i)
+                }
+            });
+        }
+        if let Some(i) = remove {
+            bookmarks.remove(i);
+        }
+        egui::CollapsingHeader::new("filters").show(ui, |ui| {
+            // only refilter when something's actually changed
+            let f = filter.bypass_change_detection();
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                ui.label("min connections:");
+                changed |= ui.add(egui::DragValue::new(&mut f.degree)).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("mutuals only:");
+                changed |= ui.checkbox(&mut f.mutuals, egui::Atoms::default()).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("followers:");
+                changed |= ui.add(egui::DragValue::new(&mut f.followers.0).range(0..=f.followers.1)).changed();
+                ui.label("to");
+                changed |= ui.add(egui::DragValue::new(&mut f.followers.1).range(f.followers.0..=i64::MAX)).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("min account age (days):");
+                changed |= ui.add(egui::DragValue::new(&mut f.age).range(0..=i64::MAX)).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("has avatar:");
+                changed |= ui.checkbox(&mut f.avatar, egui::Atoms::default()).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("hide labels:");
+                changed |= ui.add(egui::TextEdit::singleline(&mut f.labels).hint_text("porn, spam")).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("community:");
+                let text = |community: Option<usize>| match community {
+                    Some(i) => format!("#{} ({} users)", i + 1, communities[i]),
+                    None => "all".into(),
+                };
+                egui::ComboBox::from_id_salt("community").selected_text(text(f.community)).show_ui(ui, |ui| {
+                    for community in std::iter::once(None).chain((0..communities.len()).map(Some)) {
+                        changed |= ui.selectable_value(&mut f.community, community, text(community)).changed();
+                    }
+                });
+                if ui.button("detect").clicked() {
+                    commands.trigger(Detect)
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("lay out visible only:");
+                changed |= ui.checkbox(&mut f.layout, egui::Atoms::default()).changed();
+            });
+            ui.label(format!("showing {} of {}", network.len() - hidden.len(), network.len()));
+            if changed {
+                filter.set_changed()
+            }
+        });
+        if ui.button("reset").clicked() {
+            commands.trigger(Reset)
         }
     });
 }
+
+fn reset(
+    _: Trigger<Reset>,
+    mut commands: Commands,
+    mut next: ResMut<NextState<Game>>,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut search: ResMut<Search>,
+    users: Query<Entity, With<User>>,
+) {
+    bookmarks.clear();
+    commands.remove_resource::<Sim>();
+    commands.remove_resource::<Roots>();
+    commands.remove_resource::<Network>();
+    commands.remove_resource::<Lines>();
+    commands.remove_resource::<Glow>();
+    for ent in &users {
+        commands.entity(ent).despawn()
+    }
+    search.query.clear();
+    search.matches.clear();
+    next.set(Game::Ask)
+}
@@ -7,116 +7,501 @@ impl Plugin for Stuff {
         app.register_type::<Config>()
             .init_resource::<Config>()
             .add_systems(OnEnter(Game::Connect), (setup, lines))
-            .add_systems(Update, (connect, web).run_if(in_state(Game::Connect)))
-            .add_observer(rebuild)
+            .add_systems(OnExit(Game::Connect), clear)
+            .add_systems(
+                Update,
+                (
+                    (connect, grow, paint, tick, bundle, draw, glow).chain(),
+                    web,
+                )
+                    .run_if(in_state(Game::Connect)),
+            )
+            .add_observer(toggle)
+            .add_observer(relink)
             .add_observer(over)
             .add_observer(out)
-            .add_observer(link);
-    }
-}
-
-fn setup(mut commands: Commands, network: Res<Network>) {
-    let count = network.len();
-    let mut nodes = vec![fjadra::Node::default(); count];
-    nodes[count - 1] = fjadra::Node::default().fixed_position(0.0, 0.0);
-    commands.insert_resource(Sim {
-        sim: fjadra::SimulationBuilder::new()
-            .build(nodes.iter().cloned())
-            .add_force("link", fjadra::Link::new([]))
-            .add_force("charge", fjadra::ManyBody::new())
-            .add_force("centre", fjadra::Center::new()),
-        nodes,
-        links: (0..count).map(|i| (count - 1, i)).collect(),
-    });
+            .add_observer(select)
+            .add_observer(grab)
+            .add_observer(drag)
+            .add_observer(release)
+            .add_observer(middle)
+            .add_observer(unpin);
+    }
 }
 
-fn rebuild(
-    _: Trigger<Rebuild>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut sim: ResMut<Sim>,
+pub fn setup(
+    mut commands: Commands,
+    roots: Res<Roots>,
     config: Res<Config>,
-    network: Res<Network>,
-    lines: Res<Lines>,
-    users: Query<(&Transform, &User)>,
+    users: Query<(&User, &Transform)>,
 ) {
-    let Some(mesh) = meshes.get_mut(&**lines) else {
+    commands.insert_resource(Strands::default());
+    commands.insert_resource(Bundle::default());
+    let mut sim = Sim {
+        layout: Some(layout::Layout::new(config.charge, config.link)),
+        ..default()
+    };
+    // everyone else is added as the roots' follows come in
+    for (_, trans) in users.iter().sort_by_key::<&User, _>(|user| user.index) {
+        let i = sim.add(trans.translation.truncate());
+        sim.rest(i, roots.len(), config.anchored);
+    }
+    commands.insert_resource(sim);
+}
+
+use layout::Change;
+
+impl Sim {
+    // the layout might be away ticking in which case it catches up when it's back
+    pub fn change(&mut self, change: Change) {
+        match self.layout.as_mut() {
+            Some(layout) => layout.apply(change),
+            None => self.pending.push(change),
+        }
+    }
+
+    pub fn add(&mut self, pos: Vec2) -> usize {
+        self.near.push(Vec::new());
+        self.hid.push(false);
+        self.out.push(false);
+        self.change(Change::Add([pos.x as f64, pos.y as f64]));
+        self.near.len() - 1
+    }
+
+    // anchored roots go back home and everyone else goes wherever they're pulled
+    pub fn rest(&mut self, i: usize, roots: usize, anchored: bool) {
+        let home = home(i, roots);
+        let at = (i < roots && anchored).then_some([home.x as f64, home.y as f64]);
+        self.change(Change::Pin(i, at));
+    }
+
+    pub fn follow(&mut self, from: usize, to: usize) {
+        if from == to || self.index.contains_key(&(from, to)) {
+            return;
+        }
+        let l = self.links.len();
+        self.links.push((from, to));
+        self.index.insert((from, to), l);
+        self.laid.push(false);
+        self.near[from].push(l);
+        self.near[to].push(l);
+        self.fresh.push(l);
+    }
+
+    // puts a follow in or takes it out of the layout and line mesh depending on who's shown
+    fn place(
+        &mut self,
+        l: usize,
+        roots: usize,
+        timeline: &Timeline,
+        strands: &mut Strands,
+    ) -> bool {
+        let (i1, i2) = self.links[l];
+        let linked = timeline.linked(i1, i2);
+        let laid = linked && !self.out[i1] && !self.out[i2];
+        if laid != self.laid[l] {
+            self.laid[l] = laid;
+            self.change(match laid {
+                true => Change::Link(i1, i2),
+                false => Change::Unlink(i1, i2),
+            });
+        }
+        // don't really care about follows the roots share with themselves
+        match i1 >= roots && linked && !self.hid[i1] && !self.hid[i2] {
+            true => strands.show((i1, i2)),
+            false => strands.hide((i1, i2)),
+        }
+    }
+}
+
+impl Strands {
+    fn show(&mut self, (i1, i2): (usize, usize)) -> bool {
+        // only mutual if the other way round is shown too
+        let back = self.at.get(&(i2, i1)).copied();
+        let mutual = back.is_some();
+        if let Some(back) = back {
+            self.links[back].2 = true;
+        }
+        if let Some(at) = self.at.get(&(i1, i2)) {
+            let changed = self.links[*at].2 != mutual;
+            self.links[*at].2 = mutual;
+            return changed;
+        }
+        self.at.insert((i1, i2), self.links.len());
+        self.links.push((i1, i2, mutual));
+        true
+    }
+
+    fn hide(&mut self, link: (usize, usize)) -> bool {
+        let Some(at) = self.at.remove(&link) else {
+            return false;
+        };
+        self.links.swap_remove(at);
+        if let Some((i1, i2, _)) = self.links.get(at) {
+            self.at.insert((*i1, *i2), at);
+        }
+        // the other way round isn't mutual anymore
+        if let Some(back) = self.at.get(&(link.1, link.0)) {
+            self.links[*back].2 = false;
+        }
+        true
+    }
+}
+
+// places follows that have come in since last frame
+fn grow(
+    mut sim: ResMut<Sim>,
+    mut strands: ResMut<Strands>,
+    mut bundle: ResMut<Bundle>,
+    roots: Res<Roots>,
+    timeline: Res<Timeline>,
+) {
+    if sim.fresh.is_empty() {
+        return;
+    }
+    let mut changed = false;
+    for l in std::mem::take(&mut sim.fresh) {
+        changed |= sim.place(l, roots.len(), &timeline, strands.bypass_change_detection());
+    }
+    if changed {
+        strands.set_changed();
+        // bundling has to start over with different links
+        *bundle = Bundle::default();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn toggle(
+    trigger: Trigger<Toggled>,
+    sim: Option<ResMut<Sim>>,
+    mut strands: ResMut<Strands>,
+    mut bundle: ResMut<Bundle>,
+    roots: Res<Roots>,
+    filter: Res<Filter>,
+    hidden: Res<Hidden>,
+    timeline: Res<Timeline>,
+    users: Query<&User>,
+) {
+    let Some(mut sim) = sim else {
         return;
     };
-    mesh.insert_indices(bevy::render::mesh::Indices::U32(
-        sim.links
-            .iter()
-            // don't really care about follows you share with yourself
-            .skip(network.len())
-            .flat_map(|(i1, i2)| [*i1 as u32, *i2 as u32])
-            .collect(),
-    ));
-    mesh.insert_attribute(
-        Mesh::ATTRIBUTE_COLOR,
-        bevy::render::mesh::VertexAttributeValues::Float32x4(
-            users
-                .iter()
-                .sort_unstable_by_key::<&User, _>(|user: &&User| user.index)
-                .map(|(_, user)| {
-                    let fraction = user.shared.len() as f64 / network.max as f64;
-                    let colour = colorous::PLASMA.eval_continuous(fraction);
-                    [
-                        colour.r as f32 / 255.0,
-                        colour.g as f32 / 255.0,
-                        colour.b as f32 / 255.0,
-                        fraction as f32,
-                    ]
-                })
-                .collect(),
-        ),
-    );
-    for (node, (trans, _)) in sim
-        .nodes
-        .iter_mut()
-        .zip(users.iter().sort_by_key::<&User, usize>(|user| user.index))
-    {
-        // this doesn't reset fixed
-        *node =
-            std::mem::take(node).position(trans.translation.x as f64, trans.translation.y as f64);
-    }
-    **sim = fjadra::SimulationBuilder::new()
-        .build(sim.nodes.iter().cloned())
-        .add_force(
-            "link",
-            fjadra::Link::new(sim.links.iter().cloned()).distance(config.link),
-        )
-        .add_force("charge", fjadra::ManyBody::new().strength(config.charge))
-        .add_force("centre", fjadra::Center::new());
+    let mut changed = false;
+    for ent in &trigger.0 {
+        let Ok(user) = users.get(*ent) else {
+            continue;
+        };
+        let i = user.index;
+        sim.hid[i] = hidden.contains(ent);
+        // hidden users can sit still and stay out of the way
+        // and anyone playback hasn't got to yet isn't there to lay out
+        let out = sim.hid[i] && (filter.layout || !timeline.joined(*ent));
+        if out != sim.out[i] {
+            sim.out[i] = out;
+            sim.change(Change::Freeze(i, out));
+        }
+        for k in 0..sim.near[i].len() {
+            let l = sim.near[i][k];
+            changed |= sim.place(l, roots.len(), &timeline, strands.bypass_change_detection());
+        }
+    }
+    if changed {
+        strands.set_changed();
+        *bundle = Bundle::default();
+    }
+}
+
+fn relink(
+    trigger: Trigger<Relinked>,
+    sim: Option<ResMut<Sim>>,
+    mut strands: ResMut<Strands>,
+    mut bundle: ResMut<Bundle>,
+    roots: Res<Roots>,
+    timeline: Res<Timeline>,
+) {
+    let Some(mut sim) = sim else {
+        return;
+    };
+    let mut changed = false;
+    for link in &trigger.0 {
+        if let Some(l) = sim.index.get(link).copied() {
+            changed |= sim.place(l, roots.len(), &timeline, strands.bypass_change_detection());
+        }
+    }
+    if changed {
+        strands.set_changed();
+        *bundle = Bundle::default();
+    }
+}
+
+// only users that changed get recoloured unless the most follows has changed
+fn paint(
+    mut strands: ResMut<Strands>,
+    mut max: Local<usize>,
+    roots: Res<Roots>,
+    network: Res<Network>,
+    users: Query<Ref<User>>,
+) {
+    let all = network.max != *max;
+    *max = network.max;
+    let pair = compare::pair(&roots, &network);
+    let mut changed = false;
+    for user in users.iter().filter(|user| all || user.is_changed()) {
+        let strands = strands.bypass_change_detection();
+        if strands.colours.len() <= user.index {
+            strands.colours.resize(user.index + 1, [0.0; 4])
+        }
+        let fraction = user.shared.len() as f64 / network.max as f64;
+        // comparing goes by who follows them instead
+        strands.colours[user.index] = match pair.and_then(|pair| compare::side(&user, pair)) {
+            Some(side) => LinearRgba::from(side)
+                .with_alpha(fraction as f32)
+                .to_f32_array(),
+            None => {
+                let colour = colorous::PLASMA.eval_continuous(fraction);
+                [
+                    colour.r as f32 / 255.0,
+                    colour.g as f32 / 255.0,
+                    colour.b as f32 / 255.0,
+                    fraction as f32,
+                ]
+            }
+        };
+        changed = true;
+    }
+    if changed {
+        strands.set_changed()
+    }
 }
 
 fn connect(
     mut sim: ResMut<Sim>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    config: Res<Config>,
+    mut strands: ResMut<Strands>,
+    mut bundle: ResMut<Bundle>,
     mut users: Query<(&User, &mut Transform)>,
+) {
+    // ticking happens on the compute pool so big webs don't stall rendering
+    let Some(task) = sim.task.as_mut() else {
+        return;
+    };
+    let Some(mut ticked) = bevy::tasks::block_on(bevy::tasks::poll_once(task)) else {
+        return;
+    };
+    sim.task = None;
+    for change in std::mem::take(&mut sim.pending) {
+        ticked.apply(change)
+    }
+    for (user, mut trans) in &mut users {
+        if let Some([x, y]) = ticked.position(user.index) {
+            trans.translation.x = x as f32;
+            trans.translation.y = y as f32;
+        }
+    }
+    sim.layout = Some(ticked);
+    strands.set_changed();
+    bundle.fresh = false;
+}
+
+// everyone's position by index
+fn positions(users: &Query<(&User, &Transform)>) -> Vec<Vec2> {
+    let mut position = vec![Vec2::ZERO; users.iter().len()];
+    for (user, trans) in users {
+        if let Some(pos) = position.get_mut(user.index) {
+            *pos = trans.translation.truncate()
+        }
+    }
+    position
+}
+
+// only reshaped when the strands have moved or changed
+fn draw(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut directed: Local<bool>,
     lines: Res<Lines>,
+    strands: Res<Strands>,
+    config: Res<Config>,
+    bundle: Res<Bundle>,
+    users: Query<(&User, &Transform)>,
 ) {
-    if config.paused || sim.is_finished() {
+    if !strands.is_changed() && config.directed == *directed {
         return;
     }
-    sim.tick(config.speed);
-    let Some(mesh) = meshes.get_mut(&**lines) else {
+    *directed = config.directed;
+    if let Some(mesh) = meshes.get_mut(&**lines) {
+        shape(mesh, &strands, &positions(&users), &config, &bundle);
+    }
+}
+
+// how far directed edges bow out so a → b and b → a don't overlap
+const BOW: f32 = 0.15;
+const SEGMENTS: u32 = 8;
+const MUTUAL: [f32; 4] = [0.4, 1.0, 0.6, 0.6];
+
+fn shape(mesh: &mut Mesh, strands: &Strands, position: &[Vec2], config: &Config, bundle: &Bundle) {
+    use bevy::render::mesh::{Indices, VertexAttributeValues};
+    let bundled = config.bundle && bundle.fresh && bundle.lines.len() == strands.links.len();
+    // straight undirected lines can just share vertices with the orbs
+    if !config.directed && !bundled {
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            VertexAttributeValues::Float32x3(
+                position
+                    .iter()
+                    .map(|pos| pos.extend(0.0).to_array())
+                    .collect(),
+            ),
+        );
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_COLOR,
+            VertexAttributeValues::Float32x4(strands.colours.clone()),
+        );
+        mesh.insert_indices(Indices::U32(
+            strands
+                .links
+                .iter()
+                .flat_map(|(i1, i2, _)| [*i1 as u32, *i2 as u32])
+                .collect(),
+        ));
         return;
-    };
-    let cap = users.iter().count();
-    let mut position = Vec::with_capacity(cap);
-    for ([x, y], (_, mut trans)) in sim.positions().zip(
-        users
-            .iter_mut()
-            .sort_unstable_by_key::<&User, _>(|user: &&User| user.index),
-    ) {
-        trans.translation.x = x as f32;
-        trans.translation.y = y as f32;
-        position.push([x as f32, y as f32, 0.0]);
+    }
+    let mut pos = Vec::new();
+    let mut col = Vec::new();
+    let mut idx = Vec::new();
+    for (i, (i1, i2, mutual)) in strands.links.iter().enumerate() {
+        let (Some(start), Some(end)) = (position.get(*i1), position.get(*i2)) else {
+            continue;
+        };
+        let dir = end - start;
+        if config.directed && dir.length() <= config.size * 2.0 {
+            continue;
+        }
+        let mut points: Vec<Vec2> = match bundled {
+            true => {
+                let line = &bundle.lines[i];
+                let last = (line.len() - 1).max(1) as f32;
+                line.iter()
+                    .enumerate()
+                    .map(|(k, point)| {
+                        start
+                            .lerp(*end, k as f32 / last)
+                            .lerp(*point, config.bundling)
+                    })
+                    .collect()
+            }
+            false => {
+                let control = start.midpoint(*end) - dir.perp() * BOW;
+                (0..=SEGMENTS)
+                    .map(|k| {
+                        let t = k as f32 / SEGMENTS as f32;
+                        start.lerp(control, t).lerp(control.lerp(*end, t), t)
+                    })
+                    .collect()
+            }
+        };
+        let (from, to) = match config.directed && *mutual {
+            true => (MUTUAL, MUTUAL),
+            false => (strands.colours[*i1], strands.colours[*i2]),
+        };
+        let base = pos.len() as u32;
+        let last = points.len() as u32 - 1;
+        // stop at the edge of the orb so the arrowhead's visible
+        let back = (*end - points[points.len() - 2]).normalize_or_zero() * config.size;
+        if config.directed {
+            points[last as usize] = end - back;
+        }
+        for (k, point) in points.iter().enumerate() {
+            let t = k as f32 / last as f32;
+            pos.push(point.extend(0.0).to_array());
+            col.push(Vec4::from(from).lerp(Vec4::from(to), t).to_array());
+            if k != 0 {
+                idx.extend([base + k as u32 - 1, base + k as u32]);
+            }
+        }
+        if config.directed {
+            let tip = points[last as usize];
+            for side in [back.perp(), -back.perp()] {
+                pos.push((tip - back + side * 0.5).extend(0.0).to_array());
+                col.push(to);
+            }
+            let head = base + last;
+            idx.extend([head, head + 1, head, head + 2]);
+        }
     }
     mesh.insert_attribute(
         Mesh::ATTRIBUTE_POSITION,
-        bevy::render::mesh::VertexAttributeValues::Float32x3(position),
+        VertexAttributeValues::Float32x3(pos),
     );
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, VertexAttributeValues::Float32x4(col));
+    mesh.insert_indices(Indices::U32(idx));
+}
+
+fn tick(mut sim: ResMut<Sim>, config: Res<Config>) {
+    if config.paused || sim.task.is_some() {
+        return;
+    }
+    let Some(mut ticking) = sim.layout.take_if(|layout| !layout.is_finished()) else {
+        return;
+    };
+    let speed = config.speed;
+    sim.task = Some(bevy::tasks::AsyncComputeTaskPool::get().spawn(async move {
+        ticking.tick(speed);
+        ticking
+    }));
+}
+
+// bundles once the layout's settled since it's far too slow to do every tick
+fn bundle(
+    mut bundle: ResMut<Bundle>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    sim: Res<Sim>,
+    config: Res<Config>,
+    strands: Res<Strands>,
+    lines: Res<Lines>,
+    users: Query<(&User, &Transform)>,
+) {
+    if let Some(task) = bundle.task.as_mut() {
+        let Some(bundled) = bevy::tasks::block_on(bevy::tasks::poll_once(task)) else {
+            return;
+        };
+        bundle.task = None;
+        bundle.lines = bundled;
+        bundle.fresh = true;
+        // make sure it gets drawn
+        bundle.shown = (false, 0.0);
+    }
+    let settled = sim.task.is_none()
+        && sim
+            .layout
+            .as_ref()
+            .is_some_and(|layout| layout.is_finished());
+    if config.bundle && settled && !bundle.fresh {
+        let position = positions(&users);
+        let ends = strands
+            .links
+            .iter()
+            .filter_map(|(i1, i2, _)| Some((*position.get(*i1)?, *position.get(*i2)?)))
+            .collect();
+        bundle.task = Some(
+            bevy::tasks::AsyncComputeTaskPool::get().spawn(async move { bundle::bundle(ends) }),
+        );
+        return;
+    }
+    // the toggle and strength don't need the whole thing recomputed
+    if bundle.fresh && bundle.shown != (config.bundle, config.bundling) {
+        bundle.shown = (config.bundle, config.bundling);
+        if let Some(mesh) = meshes.get_mut(&**lines) {
+            shape(mesh, &strands, &positions(&users), &config, &bundle);
+        }
+    }
+}
+
+// the entities drawing the line and glow meshes
+#[derive(Component)]
+struct Thread;
+
+fn clear(mut commands: Commands, threads: Query<Entity, With<Thread>>) {
+    for ent in &threads {
+        commands.entity(ent).despawn()
+    }
 }
 
 fn lines(
@@ -129,11 +514,23 @@ fn lines(
         bevy::asset::RenderAssetUsages::default(),
     ));
     commands.spawn((
+        Thread,
         Mesh2d(lines.clone_weak()),
         MeshMaterial2d(mats.add(ColorMaterial::default())),
         Transform::from_translation(Vec3::NEG_Z),
     ));
     commands.insert_resource(Lines(lines));
+    let glow = meshes.add(Mesh::new(
+        bevy::render::mesh::PrimitiveTopology::LineList,
+        bevy::asset::RenderAssetUsages::default(),
+    ));
+    commands.spawn((
+        Thread,
+        Mesh2d(glow.clone_weak()),
+        MeshMaterial2d(mats.add(ColorMaterial::default())),
+        Transform::from_translation(Vec3::NEG_Z / 2.0),
+    ));
+    commands.insert_resource(Glow(glow));
 }
 
 fn over(
@@ -168,84 +565,338 @@ fn out(
         ));
 }
 
-fn link(trigger: Trigger<Pointer<Pressed>>, mut ctx: bevy_egui::EguiContexts, users: Query<&User>) {
+fn select(
+    trigger: Trigger<Pointer<Click>>,
+    mut ctx: bevy_egui::EguiContexts,
+    mut selected: ResMut<Selected>,
+    keys: Res<ButtonInput<KeyCode>>,
+    users: Query<(), (With<User>, Without<Grab>)>,
+) {
     if trigger.button != PointerButton::Primary {
         return;
     }
     if ctx.ctx_mut().is_ok_and(|ctx| ctx.is_pointer_over_area()) {
         return;
     }
+    let target = trigger.target();
+    if !users.contains(target) {
+        return;
+    }
+    // shift toggles users in and out of a multi-selection
+    if !keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        **selected = vec![target];
+    } else if let Some(i) = selected.iter().position(|ent| *ent == target) {
+        selected.remove(i);
+    } else {
+        selected.push(target)
+    }
+}
+
+fn grab(
+    trigger: Trigger<Pointer<DragStart>>,
+    mut commands: Commands,
+    users: Query<&Transform, With<User>>,
+) {
+    if trigger.button != PointerButton::Primary {
+        return;
+    }
+    let Ok(trans) = users.get(trigger.target()) else {
+        return;
+    };
+    commands
+        .entity(trigger.target())
+        .insert((Grab, Pinned(trans.translation.truncate())));
+}
+
+fn drag(
+    trigger: Trigger<Pointer<Drag>>,
+    mut sim: ResMut<Sim>,
+    mut users: Query<(&User, &mut Pinned, &mut Transform), With<Grab>>,
+    proj: Single<&Projection>,
+) {
+    let Projection::Orthographic(proj) = &**proj else {
+        return;
+    };
+    let Ok((user, mut pin, mut trans)) = users.get_mut(trigger.target()) else {
+        return;
+    };
+    pin.x += trigger.delta.x * proj.scale;
+    pin.y -= trigger.delta.y * proj.scale;
+    trans.translation.x = pin.x;
+    trans.translation.y = pin.y;
+    // reheating lets the neighbours follow along
+    sim.change(Change::Pin(user.index, Some([pin.x as f64, pin.y as f64])));
+}
+
+fn release(
+    trigger: Trigger<Pointer<DragEnd>>,
+    mut commands: Commands,
+    config: Res<Config>,
+    users: Query<(), With<Grab>>,
+) {
+    if !users.contains(trigger.target()) {
+        return;
+    }
+    commands.entity(trigger.target()).remove::<Grab>();
+    if !config.pin {
+        commands.trigger_targets(Unpin, trigger.target())
+    }
+}
+
+fn middle(trigger: Trigger<Pointer<Pressed>>, mut commands: Commands) {
+    if trigger.button == PointerButton::Middle {
+        commands.trigger_targets(Unpin, trigger.target())
+    }
+}
+
+fn unpin(
+    trigger: Trigger<Unpin>,
+    mut commands: Commands,
+    mut sim: ResMut<Sim>,
+    roots: Res<Roots>,
+    config: Res<Config>,
+    users: Query<&User, With<Pinned>>,
+) {
     let Ok(user) = users.get(trigger.target()) else {
         return;
     };
-    let _ = webbrowser::open(&format!("https://bsky.app/profile/{}", user.handle));
+    sim.rest(user.index, roots.len(), config.anchored);
+    commands.entity(trigger.target()).remove::<Pinned>();
+}
+
+// a user's follows and followers, coloured by which way they go
+fn strand(
+    (positions, colours): (&mut Vec<[f32; 3]>, &mut Vec<[f32; 4]>),
+    users: &Query<(&User, &Transform)>,
+    hidden: &Hidden,
+    ent: Entity,
+    only: Option<&std::collections::HashSet<Entity>>,
+) {
+    let Ok((user, trans)) = users.get(ent) else {
+        return;
+    };
+    let fans: std::collections::HashSet<_> = user.fans.iter().collect();
+    let follows: std::collections::HashSet<_> = user.shared.iter().collect();
+    for (other, colour) in user
+        .shared
+        .iter()
+        .map(|other| match fans.contains(other) {
+            true => (other, LinearRgba::GREEN),
+            false => (other, LinearRgba::BLUE),
+        })
+        .chain(
+            user.fans
+                .iter()
+                .filter(|other| !follows.contains(other))
+                .map(|other| (other, LinearRgba::RED)),
+        )
+    {
+        if hidden.contains(other) || only.is_some_and(|only| !only.contains(other)) {
+            continue;
+        }
+        let Ok((_, trans2)) = users.get(*other) else {
+            continue;
+        };
+        positions.extend([trans.translation.to_array(), trans2.translation.to_array()]);
+        colours.extend([colour.to_f32_array(); 2]);
+    }
+}
+
+// only redrawn when what's highlighted or where it is changes
+#[allow(clippy::too_many_arguments)]
+fn glow(
+    mut meshes: ResMut<Assets<Mesh>>,
+    glow: Res<Glow>,
+    selected: Res<Selected>,
+    ego: Res<Ego>,
+    route: Res<Route>,
+    hidden: Res<Hidden>,
+    mut drawn: Local<Vec<[f32; 3]>>,
+    mut tints: Local<Vec<[f32; 4]>>,
+    interactions: Query<&bevy::picking::pointer::PointerInteraction>,
+    users: Query<(&User, &Transform)>,
+) {
+    let hover = interactions
+        .iter()
+        .filter_map(bevy::picking::pointer::PointerInteraction::get_nearest_hit)
+        .map(|(ent, _)| *ent)
+        .find(|ent| users.contains(*ent));
+    let mut positions = Vec::new();
+    let mut colours = Vec::new();
+    // the selection's neighbourhood stays lit up
+    for ent in selected.iter() {
+        strand(
+            (&mut positions, &mut colours),
+            &users,
+            &hidden,
+            *ent,
+            Some(&ego.users),
+        );
+    }
+    for pair in route.path.as_deref().unwrap_or_default().windows(2) {
+        let (Ok((_, trans)), Ok((_, trans2))) = (users.get(pair[0]), users.get(pair[1])) else {
+            continue;
+        };
+        positions.extend([trans.translation.to_array(), trans2.translation.to_array()]);
+        colours.extend([LinearRgba::rgb(1.0, 1.0, 0.0).to_f32_array(); 2]);
+    }
+    if let Some(ent) = hover {
+        strand((&mut positions, &mut colours), &users, &hidden, ent, None);
+    }
+    // the rest of the web moving around doesn't matter
+    if *drawn == positions && *tints == colours {
+        return;
+    }
+    let Some(mesh) = meshes.get_mut(&**glow) else {
+        return;
+    };
+    drawn.clone_from(&positions);
+    tints.clone_from(&colours);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colours);
 }
 
+#[allow(clippy::too_many_arguments)]
 fn web(
-    mut gizmo: Gizmos,
     mut ctx: bevy_egui::EguiContexts,
+    config: Res<Config>,
+    roots: Res<Roots>,
     network: Res<Network>,
+    mats: Res<Assets<ColorMaterial>>,
     interactions: Query<&bevy::picking::pointer::PointerInteraction>,
-    users: Query<(&User, &Transform)>,
+    users: Query<(
+        Entity,
+        &User,
+        &Transform,
+        &InheritedVisibility,
+        &MeshMaterial2d<ColorMaterial>,
+    )>,
     proj: Single<(&Transform, &Projection)>,
 ) {
     use bevy_egui::egui;
+    let hover = interactions
+        .iter()
+        .filter_map(bevy::picking::pointer::PointerInteraction::get_nearest_hit)
+        .find_map(|(ent, _)| users.get(*ent).ok());
+    let avatar = hover
+        .and_then(|(_, _, _, _, mat)| mats.get(&mat.0))
+        .and_then(|mat| mat.texture.as_ref())
+        .map(|texture| ctx.add_image(texture.clone_weak()));
     let Ok(ctx) = ctx.ctx_mut() else { return };
     let (camera, Projection::Orthographic(proj)) = &*proj else {
         return;
     };
-    for (ent, _) in interactions
+    let mut top: Vec<_> = users
         .iter()
-        .filter_map(bevy::picking::pointer::PointerInteraction::get_nearest_hit)
-    {
-        let Ok((user, trans)) = users.get(*ent) else {
-            continue;
-        };
-        let dim = ctx.available_rect();
-        let text = egui::style::default_text_styles()[&egui::TextStyle::Body].clone();
-        let pad = ctx.style().spacing.window_margin.bottomf();
+        .filter(|(_, _, _, vis, _)| vis.get())
+        .map(|(_, user, trans, _, _)| {
+            let score = match config.rank {
+                Rank::Followed => user.fans.len(),
+                Rank::Follows => user.shared.len(),
+                Rank::Degree => user.fans.len() + user.shared.len(),
+            };
+            (score, user, trans)
+        })
+        .collect();
+    let labelled = config.labelled.min(top.len());
+    if labelled > 0 {
+        top.select_nth_unstable_by(labelled - 1, |(a, ..), (b, ..)| b.cmp(a));
+    }
+    let dim = ctx.available_rect();
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    let pad = ctx.style().spacing.button_padding;
+    for (_, user, trans) in &top[..labelled] {
         let pos = egui::pos2(
             (trans.translation.x - camera.translation.x) / proj.scale + dim.width() / 2.0,
             (-trans.translation.y + camera.translation.y) / proj.scale + dim.height() / 2.0,
         );
-        let mut start = pos.clone();
-        start.x -= pad;
-        start.y -= pad;
-        let mut end = pos.clone();
-        end.x += ctx.fonts(|fonts| {
-            user.handle
-                .chars()
-                .fold(0.0, |acc, c| acc + fonts.glyph_width(&text, c))
-        }) + pad;
-        end.y += text.size + pad;
-        ctx.debug_painter().rect_filled(
-            egui::Rect::from_min_max(start, end),
-            ctx.style().visuals.window_corner_radius,
-            ctx.style().visuals.widgets.noninteractive.bg_fill,
+        let name = match user.name().is_empty() {
+            true => user.handle.to_string(),
+            false => user.name().to_owned(),
+        };
+        let galley = painter.layout_no_wrap(
+            name,
+            egui::TextStyle::Small.resolve(&ctx.style()),
+            ctx.style().visuals.text_color(),
+        );
+        // just under the orb
+        let rect = egui::Align2::CENTER_TOP.anchor_size(
+            pos + egui::vec2(0.0, config.size / proj.scale + pad.y),
+            galley.size(),
         );
-        ctx.debug_painter().text(
-            pos,
-            egui::Align2::LEFT_TOP,
-            user.handle.clone(),
-            text,
-            ctx.style().visuals.noninteractive().text_color(),
+        painter.rect_filled(
+            rect.expand2(pad),
+            ctx.style().visuals.menu_corner_radius,
+            ctx.style().visuals.extreme_bg_color.gamma_multiply(0.8),
         );
-        for (ent2, (user2, trans2)) in network
-            .iter()
-            .filter_map(|(_, ent)| Some((ent, users.get(*ent).ok()?)))
-        {
-            let follows = user.shared.contains(ent2);
-            let followed = user2.shared.contains(ent);
-            gizmo.line(
-                trans.translation,
-                trans2.translation,
-                match (follows, followed) {
-                    (true, true) => LinearRgba::GREEN,
-                    (true, false) => LinearRgba::BLUE,
-                    (false, true) => LinearRgba::RED,
-                    (false, false) => continue,
-                },
-            )
-        }
+        painter.galley(rect.min, galley, egui::Color32::PLACEHOLDER);
     }
+    let Some((ent, user, ..)) = hover else {
+        return;
+    };
+    let you: Vec<_> = roots.ents(&network).collect();
+    egui::Tooltip::always_open(
+        ctx.clone(),
+        egui::LayerId::background(),
+        egui::Id::new("hover"),
+        egui::PopupAnchor::Pointer,
+    )
+    .show(|ui| {
+        ui.horizontal(|ui| {
+            if let Some(avatar) = avatar {
+                ui.image(egui::load::SizedTexture::new(avatar, [32.0, 32.0]));
+            }
+            ui.vertical(|ui| {
+                if !user.name().is_empty() {
+                    ui.strong(user.name());
+                }
+                ui.label(format!("@{}", user.handle.as_str()));
+            });
+        });
+        if let Some(bio) = &user.description {
+            // a few lines is plenty for a tooltip
+            let mut short: String = bio.chars().take(140).collect();
+            if short.len() < bio.len() {
+                short.push('…')
+            }
+            ui.label(short);
+        }
+        if you.first() == Some(&ent) {
+            ui.label("that's you!");
+            return;
+        }
+        let follows = user.shared.iter().filter(|ent| !you.contains(ent)).count();
+        let followed = user.fans.iter().filter(|ent| !you.contains(ent)).count();
+        ui.label(format!(
+            "follows {follows} of the {} others in the web",
+            network.len() - you.len()
+        ));
+        ui.label(format!("followed by {followed} of them"));
+        match &you[..] {
+            [you] => {
+                if user.shared.contains(you) {
+                    ui.label("follows you");
+                }
+            }
+            // too many to list one by one
+            you if you.len() > 2 => {
+                let fans = you.iter().filter(|you| user.fans.contains(you)).count();
+                let follows = you.iter().filter(|you| user.shared.contains(you)).count();
+                ui.label(format!("followed by {fans} of the {} roots", you.len()));
+                ui.label(format!("follows {follows} of them"));
+            }
+            you => {
+                for (root, other) in roots.iter().zip(you) {
+                    let (to, from) = (user.shared.contains(other), user.fans.contains(other));
+                    let text = match (to, from) {
+                        (true, true) => "mutuals with",
+                        (true, false) => "follows",
+                        (false, true) => "followed by",
+                        (false, false) => continue,
+                    };
+                    ui.label(format!("{text} @{}", root.handle.as_str()));
+                }
+            }
+        }
+    });
 }
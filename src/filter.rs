@@ -0,0 +1,361 @@
+use super::*;
+use atrium_api::app::bsky::actor::get_profiles;
+use atrium_api::types::string::AtIdentifier;
+
+pub struct Stuff;
+
+impl Plugin for Stuff {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Filter>()
+            .init_resource::<Hidden>()
+            .init_resource::<Communities>()
+            .init_resource::<Tally>()
+            .add_observer(detect)
+            .add_systems(
+                Update,
+                (tally, filter).chain().run_if(in_state(Game::Connect)),
+            )
+            .add_systems(OnExit(Game::Connect), clear);
+    }
+}
+
+// follower counts aren't in the follow lists so they're fetched in batches
+#[derive(Resource, Default)]
+struct Tally {
+    started: bool,
+    queue: Vec<AtIdentifier>,
+    // failed batches with when to try them again and how many times they've failed
+    retry: Vec<(f32, usize, Vec<AtIdentifier>)>,
+    tasks: Vec<(Vec<AtIdentifier>, usize, Batch)>,
+}
+
+type Batch = bevy::tasks::Task<atrium_api::xrpc::Result<get_profiles::Output, get_profiles::Error>>;
+
+fn clear(mut commands: Commands) {
+    commands.insert_resource(Hidden::default());
+    commands.insert_resource(Communities::default());
+    commands.insert_resource(Tally::default());
+}
+
+fn tally(
+    time: Res<Time>,
+    filter: Res<Filter>,
+    network: Res<Network>,
+    mut tally: ResMut<Tally>,
+    mut users: Query<&mut User>,
+) {
+    if !tally.started {
+        if filter.followers == Filter::default().followers {
+            return;
+        }
+        tally.started = true;
+        tally.queue = users
+            .iter()
+            .map(|user| AtIdentifier::Did(user.did.clone()))
+            .collect();
+    }
+    let now = time.elapsed_secs();
+    let mut retry = Vec::new();
+    tally.tasks.retain_mut(|(actors, fails, task)| {
+        match bevy::tasks::block_on(bevy::tasks::poll_once(task)) {
+            Some(Ok(atrium_api::types::Object { data, .. })) => {
+                for profile in data.profiles {
+                    if let Some(mut user) = network
+                        .get(&profile.did)
+                        .and_then(|ent| users.get_mut(*ent).ok())
+                    {
+                        user.followers = profile.followers_count
+                    }
+                }
+                false
+            }
+            // they just stay unfiltered by followers
+            Some(Err(_)) if *fails + 1 >= bsky::RETRIES => false,
+            Some(Err(_)) => {
+                // waits longer each time so a rate limit has a chance to wear off
+                retry.push((
+                    now + 2f32.powi(*fails as i32),
+                    *fails + 1,
+                    std::mem::take(actors),
+                ));
+                false
+            }
+            None => true,
+        }
+    });
+    tally.retry.append(&mut retry);
+    // a few at a time so we don't get rate limited
+    while tally.tasks.len() < 4 {
+        let (actors, fails) = match tally.retry.iter().position(|(when, ..)| *when <= now) {
+            Some(i) => {
+                let (_, fails, actors) = tally.retry.swap_remove(i);
+                (actors, fails)
+            }
+            None if !tally.queue.is_empty() => {
+                let len = tally.queue.len().min(25);
+                (tally.queue.drain(..len).collect(), 0)
+            }
+            None => break,
+        };
+        let task = bevy::tasks::IoTaskPool::get().spawn(Compat::new(
+            CLIENT.service.app.bsky.actor.get_profiles(
+                get_profiles::ParametersData {
+                    actors: actors.clone(),
+                }
+                .into(),
+            ),
+        ));
+        tally.tasks.push((actors, fails, task));
+    }
+}
+
+// how long to wait between working out who's filtered while the crawl is still going
+const THROTTLE: f32 = 0.5;
+
+// what filter keeps between runs so it doesn't redo everything each frame
+#[derive(Default)]
+struct Kept {
+    out: std::collections::HashSet<Entity>,
+    // follows within the web either way
+    degree: std::collections::HashMap<Entity, usize>,
+    // how much of each user's shared is already in degree
+    counted: std::collections::HashMap<Entity, usize>,
+    // users have changed since out was worked out
+    stale: bool,
+    // when out can next be worked out while crawling
+    next: f32,
+    layout: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn filter(
+    mut commands: Commands,
+    time: Res<Time>,
+    filter: Res<Filter>,
+    progress: Res<Progress>,
+    mut kept: Local<Kept>,
+    mut hidden: ResMut<Hidden>,
+    roots: Res<Roots>,
+    network: Res<Network>,
+    timeline: Res<Timeline>,
+    users: Query<(Entity, Ref<User>)>,
+) {
+    // a new web starts from nothing
+    let all = network.is_added();
+    if all {
+        *kept = default();
+    }
+    // follows only ever get added so just the new ones are counted
+    for (ent, user) in users.iter().filter(|(_, user)| all || user.is_changed()) {
+        let counted = kept.counted.entry(ent).or_default();
+        let new = user.shared[*counted..].to_vec();
+        *counted = user.shared.len();
+        *kept.degree.entry(ent).or_default() += new.len();
+        for other in new {
+            *kept.degree.entry(other).or_default() += 1;
+        }
+        kept.stale = true;
+    }
+    let now = time.elapsed_secs();
+    let crawling = progress.fetching || progress.crawling > 0;
+    // nobody's filtered out by default so there's nothing to work out
+    let due = kept.stale && filter.active() && (!crawling || now >= kept.next);
+    let you: Vec<_> = roots.ents(&network).collect();
+    if filter.is_changed() || due {
+        kept.out = kept_out(&filter, &you, &kept.degree, &users);
+        kept.stale = false;
+        kept.next = now + THROTTLE;
+    } else if !timeline.is_changed() {
+        return;
+    }
+    let mut filtered = kept.out.clone();
+    filtered.extend(
+        timeline
+            .users
+            .keys()
+            .filter(|ent| !timeline.joined(**ent) && !you.contains(ent)),
+    );
+    let mut toggled: Vec<_> = filtered.symmetric_difference(&hidden).cloned().collect();
+    // everyone hidden either comes out of or goes back into the layout
+    if filter.layout != kept.layout {
+        kept.layout = filter.layout;
+        toggled = filtered.union(&hidden).cloned().collect();
+    }
+    if filtered != **hidden {
+        **hidden = filtered;
+    }
+    if !toggled.is_empty() {
+        commands.trigger(Toggled(toggled))
+    }
+}
+
+// everyone the filters leave out whatever the timeline's at
+fn kept_out(
+    filter: &Filter,
+    you: &[Entity],
+    degree: &std::collections::HashMap<Entity, usize>,
+    users: &Query<(Entity, Ref<User>)>,
+) -> std::collections::HashSet<Entity> {
+    let labels: Vec<_> = filter
+        .labels
+        .split(',')
+        .map(str::trim)
+        .filter(|label| !label.is_empty())
+        .collect();
+    let now = atrium_api::types::string::Datetime::now()
+        .as_ref()
+        .timestamp();
+    users
+        .iter()
+        .filter(|(ent, user)| {
+            // the roots are always there to hold the web together
+            if you.contains(ent) {
+                return false;
+            }
+            let (min, max) = filter.followers;
+            degree.get(ent).is_none_or(|degree| *degree < filter.degree)
+                // without roots it's mutuals with anyone
+                || filter.mutuals
+                    && !match you.is_empty() {
+                        true => &user.shared[..],
+                        false => you,
+                    }
+                    .iter()
+                    .any(|you| user.shared.contains(you) && user.fans.contains(you))
+                || user
+                    .followers
+                    .is_some_and(|followers| followers < min || followers > max)
+                || filter.age > 0
+                    && user.created_at.as_ref().is_none_or(|created| {
+                        (now - created.as_ref().timestamp()) / 86400 < filter.age
+                    })
+                || filter.avatar && user.avatar.is_none()
+                || user.labels().any(|label| labels.contains(&label))
+                || filter
+                    .community
+                    .is_some_and(|community| user.community != Some(community))
+        })
+        .map(|(ent, _)| ent)
+        .collect()
+}
+
+// label propagation where everyone takes on the most common label around them
+fn detect(
+    _: Trigger<Detect>,
+    mut communities: ResMut<Communities>,
+    mut users: Query<(Entity, &mut User)>,
+) {
+    let index: std::collections::HashMap<Entity, usize> =
+        users.iter().map(|(ent, user)| (ent, user.index)).collect();
+    let count = index.len();
+    let mut near = vec![Vec::new(); count];
+    for (_, user) in &users {
+        for other in user.shared.iter().filter_map(|ent| index.get(ent)) {
+            near[user.index].push(*other);
+            near[*other].push(user.index);
+        }
+    }
+    let mut labels: Vec<usize> = (0..count).collect();
+    let mut order: Vec<usize> = (0..count).collect();
+    let mut seed = 0x2545f4914f6cdd1d_u64;
+    for _ in 0..20 {
+        // visiting in a shuffled order stops one label flooding everything
+        for i in (1..count).rev() {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            order.swap(i, seed as usize % (i + 1));
+        }
+        let mut settled = true;
+        for &i in &order {
+            let mut counts: std::collections::HashMap<usize, usize> = default();
+            for other in &near[i] {
+                *counts.entry(labels[*other]).or_default() += 1;
+            }
+            let Some((label, _)) = counts
+                .into_iter()
+                .max_by_key(|(label, count)| (*count, std::cmp::Reverse(*label)))
+            else {
+                continue;
+            };
+            if label != labels[i] {
+                labels[i] = label;
+                settled = false;
+            }
+        }
+        if settled {
+            break;
+        }
+    }
+    // number communities from biggest to smallest
+    let mut sizes: std::collections::HashMap<usize, usize> = default();
+    for label in &labels {
+        *sizes.entry(*label).or_default() += 1;
+    }
+    let mut sizes: Vec<_> = sizes.into_iter().collect();
+    sizes.sort_unstable_by_key(|(label, size)| (std::cmp::Reverse(*size), *label));
+    let ids: std::collections::HashMap<usize, usize> = sizes
+        .iter()
+        .enumerate()
+        .map(|(id, (label, _))| (*label, id))
+        .collect();
+    **communities = sizes.into_iter().map(|(_, size)| size).collect();
+    for (_, mut user) in &mut users {
+        user.community = Some(ids[&labels[user.index]]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detected(links: &[(usize, usize)], count: usize) -> (Vec<Option<usize>>, Vec<usize>) {
+        let mut world = World::new();
+        world.init_resource::<Communities>();
+        world.add_observer(detect);
+        let ents: Vec<_> = (0..count).map(|i| world.spawn(dummy(i)).id()).collect();
+        for (from, to) in links {
+            world
+                .get_mut::<User>(ents[*from])
+                .unwrap()
+                .shared
+                .push(ents[*to]);
+        }
+        world.trigger(Detect);
+        world.flush();
+        let communities = ents
+            .iter()
+            .map(|ent| world.get::<User>(*ent).unwrap().community)
+            .collect();
+        (communities, world.resource::<Communities>().0.clone())
+    }
+
+    #[test]
+    fn cliques() {
+        // two tight groups with a single follow between them
+        let mut links = Vec::new();
+        for group in [0..5, 5..9] {
+            for i in group.clone() {
+                for j in group.clone().filter(|j| *j != i) {
+                    links.push((i, j))
+                }
+            }
+        }
+        links.push((4, 5));
+        let (communities, sizes) = detected(&links, 9);
+        // biggest first
+        assert_eq!(sizes, [5, 4]);
+        assert!(communities[..5].iter().all(|c| *c == Some(0)));
+        assert!(communities[5..].iter().all(|c| *c == Some(1)));
+    }
+
+    #[test]
+    fn alone() {
+        // nobody around means everyone's their own community
+        let (communities, sizes) = detected(&[], 3);
+        assert_eq!(sizes, [1, 1, 1]);
+        let mut ids: Vec<_> = communities.into_iter().flatten().collect();
+        ids.sort_unstable();
+        assert_eq!(ids, [0, 1, 2]);
+    }
+}
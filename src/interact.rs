@@ -0,0 +1,417 @@
+use super::*;
+use atrium_api::app::bsky::feed::defs::FeedViewPostReasonRefs;
+use atrium_api::app::bsky::feed::post::RecordEmbedRefs;
+use atrium_api::app::bsky::feed::{get_author_feed, get_likes, post};
+use atrium_api::app::bsky::richtext::facet::MainFeaturesItem;
+use atrium_api::types::string::{AtIdentifier, Datetime, Did};
+use atrium_api::types::{TryFromUnknown, Union};
+
+pub struct Stuff;
+
+impl Plugin for Stuff {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Interactions>()
+            .add_systems(OnEnter(Game::Connect), spawn)
+            .add_systems(
+                Update,
+                (fetch, weigh, weave)
+                    .chain()
+                    .run_if(in_state(Game::Connect)),
+            )
+            .add_systems(
+                bevy_egui::EguiPrimaryContextPass,
+                interactions.run_if(in_state(Game::Connect)),
+            )
+            .add_systems(OnExit(Game::Connect), clear);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Reply,
+    Quote,
+    Mention,
+    Repost,
+    Like,
+}
+
+impl Kind {
+    // the more effort it takes the more it counts
+    fn weight(self) -> f32 {
+        match self {
+            Kind::Reply | Kind::Quote => 3.0,
+            Kind::Mention | Kind::Repost => 2.0,
+            Kind::Like => 1.0,
+        }
+    }
+}
+
+// who interacted with who, how and when
+type Hit = (Did, Did, Kind, i64);
+
+#[derive(Resource)]
+struct Interactions {
+    shown: bool,
+    // how many days back count with 0 being forever
+    days: i64,
+    crawl: Crawl<Hit>,
+    // kept by did since whoever they're with might not be in the web yet
+    hits: Vec<Hit>,
+    // both ways added together within the window
+    weights: Vec<(Entity, Entity, f32)>,
+    dirty: bool,
+}
+
+impl Default for Interactions {
+    fn default() -> Self {
+        Self {
+            shown: false,
+            days: 30,
+            crawl: default(),
+            hits: Vec::new(),
+            weights: Vec::new(),
+            dirty: false,
+        }
+    }
+}
+
+// the interaction mesh
+#[derive(Component, Deref)]
+struct Weave(Handle<Mesh>);
+
+fn spawn(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut mats: ResMut<Assets<ColorMaterial>>,
+) {
+    let weave = meshes.add(Mesh::new(
+        bevy::render::mesh::PrimitiveTopology::TriangleList,
+        bevy::asset::RenderAssetUsages::default(),
+    ));
+    commands.spawn((
+        Mesh2d(weave.clone_weak()),
+        MeshMaterial2d(mats.add(ColorMaterial::default())),
+        Transform::from_translation(Vec3::NEG_Z * 0.75),
+        Visibility::Hidden,
+        Weave(weave),
+    ));
+}
+
+fn clear(mut commands: Commands, weave: Query<Entity, With<Weave>>) {
+    commands.insert_resource(Interactions::default());
+    for ent in &weave {
+        commands.entity(ent).despawn()
+    }
+}
+
+// a few pages of each feed is plenty to see who talks to who
+const PAGES: usize = 3;
+// likes are a request per post so only the latest few are looked at
+const LIKED: usize = 10;
+
+fn fetch(mut inter: ResMut<Interactions>, users: Query<(Entity, &User)>) {
+    // nothing's fetched until it's asked for
+    if !inter.shown && inter.crawl.seen.is_empty() {
+        return;
+    }
+    // nothing drawn changes until the hits are weighed
+    let inter = inter.bypass_change_detection();
+    let mut hits = inter.crawl.poll(users.iter(), feed);
+    inter.dirty |= !hits.is_empty();
+    inter.hits.append(&mut hits);
+}
+
+// the did that owns an at-uri
+fn owner(uri: &str) -> Option<Did> {
+    uri.strip_prefix("at://")?.split('/').next()?.parse().ok()
+}
+
+fn time(when: &Datetime) -> i64 {
+    when.as_ref().timestamp()
+}
+
+async fn feed(did: Did) -> Result<Vec<Hit>, String> {
+    let mut hits = Vec::new();
+    let mut posts = Vec::new();
+    let mut cursor = None;
+    for _ in 0..PAGES {
+        let data = CLIENT
+            .service
+            .app
+            .bsky
+            .feed
+            .get_author_feed(
+                get_author_feed::ParametersData {
+                    actor: AtIdentifier::Did(did.clone()),
+                    cursor,
+                    filter: Some("posts_with_replies".into()),
+                    include_pins: None,
+                    limit: 100.try_into().ok(),
+                }
+                .into(),
+            )
+            .await
+            .map_err(|e| e.to_string())?
+            .data;
+        for item in data.feed {
+            let post = &item.post;
+            if let Some(Union::Refs(FeedViewPostReasonRefs::ReasonRepost(reason))) = &item.reason {
+                hits.push((
+                    did.clone(),
+                    post.author.did.clone(),
+                    Kind::Repost,
+                    time(&reason.indexed_at),
+                ));
+                continue;
+            }
+            if post.author.did != did {
+                continue;
+            }
+            let Ok(record) = post::Record::try_from_unknown(post.record.clone()) else {
+                continue;
+            };
+            let when = time(&record.created_at);
+            if let Some(to) = record
+                .reply
+                .as_ref()
+                .and_then(|reply| owner(&reply.parent.uri))
+            {
+                hits.push((did.clone(), to, Kind::Reply, when))
+            }
+            let quoted = match &record.embed {
+                Some(Union::Refs(RecordEmbedRefs::AppBskyEmbedRecordMain(embed))) => {
+                    Some(&embed.record.uri)
+                }
+                Some(Union::Refs(RecordEmbedRefs::AppBskyEmbedRecordWithMediaMain(embed))) => {
+                    Some(&embed.record.record.uri)
+                }
+                _ => None,
+            };
+            if let Some(to) = quoted.and_then(|uri| owner(uri)) {
+                hits.push((did.clone(), to, Kind::Quote, when))
+            }
+            for feature in record
+                .facets
+                .iter()
+                .flatten()
+                .flat_map(|facet| &facet.features)
+            {
+                if let Union::Refs(MainFeaturesItem::Mention(mention)) = feature {
+                    hits.push((did.clone(), mention.did.clone(), Kind::Mention, when))
+                }
+            }
+            if posts.len() < LIKED {
+                posts.push(post.uri.clone())
+            }
+        }
+        cursor = data.cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    for uri in posts {
+        // missing likes don't make the rest any less right
+        let Ok(output) = CLIENT
+            .service
+            .app
+            .bsky
+            .feed
+            .get_likes(
+                get_likes::ParametersData {
+                    cid: None,
+                    cursor: None,
+                    limit: 100.try_into().ok(),
+                    uri,
+                }
+                .into(),
+            )
+            .await
+        else {
+            continue;
+        };
+        for like in output.data.likes {
+            hits.push((
+                like.actor.did.clone(),
+                did.clone(),
+                Kind::Like,
+                time(&like.created_at),
+            ))
+        }
+    }
+    Ok(hits)
+}
+
+fn weigh(mut inter: ResMut<Interactions>, network: Res<Network>, timeline: Res<Timeline>) {
+    if !inter.dirty && !network.is_changed() && !timeline.is_changed() {
+        return;
+    }
+    inter.dirty = false;
+    // playing back goes by how far it's got instead of today
+    let now = timeline.at.unwrap_or_else(|| time(&Datetime::now()));
+    let since = match inter.days {
+        0 => i64::MIN,
+        days => now - days * 86400,
+    };
+    let mut weights: std::collections::HashMap<(Entity, Entity), f32> = default();
+    for (from, to, kind, when) in &inter.hits {
+        if *when < since || *when > now || from == to {
+            continue;
+        }
+        let (Some(a), Some(b)) = (network.get(from), network.get(to)) else {
+            continue;
+        };
+        if !timeline.joined(*a) || !timeline.joined(*b) {
+            continue;
+        }
+        // which way it went doesn't matter for how thick it's drawn
+        *weights.entry((*a.min(b), *a.max(b))).or_default() += kind.weight();
+    }
+    inter.weights = weights
+        .into_iter()
+        .map(|((a, b), weight)| (a, b, weight))
+        .collect();
+}
+
+// only rebuilt when the weights, what's hidden or where anyone is changes
+#[allow(clippy::too_many_arguments)]
+fn weave(
+    inter: Res<Interactions>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    config: Res<Config>,
+    hidden: Res<Hidden>,
+    (lines, glow): (Res<Lines>, Res<Glow>),
+    (roots, network): (Res<Roots>, Res<Network>),
+    mut weave: Single<(&Weave, &mut Visibility)>,
+    mut strands: Query<(&Mesh2d, &mut Visibility), Without<Weave>>,
+    users: Query<(&User, &Transform)>,
+    moved: Query<(), (With<User>, Changed<Transform>)>,
+    followed: Query<(), Changed<User>>,
+    (mut shown, mut size): (Local<bool>, Local<f32>),
+) {
+    let (weave, visibility) = &mut *weave;
+    // follows make way for interactions and come back after
+    if *shown != inter.shown {
+        *shown = inter.shown;
+        **visibility = match inter.shown {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+        for (_, mut visibility) in strands
+            .iter_mut()
+            .filter(|(mesh, _)| mesh.0 == **lines || mesh.0 == **glow)
+        {
+            *visibility = match inter.shown {
+                true => Visibility::Hidden,
+                false => Visibility::Inherited,
+            }
+        }
+    }
+    if !inter.shown
+        || !inter.is_changed()
+            && !hidden.is_changed()
+            && *size == config.size
+            && moved.is_empty()
+            && followed.is_empty()
+    {
+        return;
+    }
+    *size = config.size;
+    let Some(mesh) = meshes.get_mut(&***weave) else {
+        return;
+    };
+    use bevy::render::mesh::{Indices, VertexAttributeValues};
+    let max = inter
+        .weights
+        .iter()
+        .map(|(_, _, weight)| *weight)
+        .fold(1.0, f32::max);
+    let pair = compare::pair(&roots, &network);
+    let mut pos = Vec::new();
+    let mut col = Vec::new();
+    let mut idx = Vec::new();
+    for (a, b, weight) in &inter.weights {
+        if hidden.contains(a) || hidden.contains(b) {
+            continue;
+        }
+        let (Ok((ua, start)), Ok((ub, end))) = (users.get(*a), users.get(*b)) else {
+            continue;
+        };
+        let (start, end) = (start.translation.truncate(), end.translation.truncate());
+        let fraction = weight / max;
+        // square root so the odd huge thread doesn't make everything else a hairline
+        let side = (end - start).normalize_or_zero().perp() * config.size * fraction.sqrt() / 2.0;
+        let alpha = 0.3 + fraction * 0.7;
+        // comparing goes by who follows either of them like the follows do
+        let colour = match pair.and_then(|pair| compare::span(ua, ub, pair)) {
+            Some(side) => LinearRgba::from(side).with_alpha(alpha).to_f32_array(),
+            None => {
+                let colour = colorous::PLASMA.eval_continuous(fraction as f64);
+                [
+                    colour.r as f32 / 255.0,
+                    colour.g as f32 / 255.0,
+                    colour.b as f32 / 255.0,
+                    alpha,
+                ]
+            }
+        };
+        let base = pos.len() as u32;
+        for point in [start + side, start - side, end + side, end - side] {
+            pos.push(point.extend(0.0).to_array());
+            col.push(colour);
+        }
+        idx.extend([base, base + 1, base + 2, base + 2, base + 1, base + 3]);
+    }
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        VertexAttributeValues::Float32x3(pos),
+    );
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, VertexAttributeValues::Float32x4(col));
+    mesh.insert_indices(Indices::U32(idx));
+}
+
+fn interactions(mut ctx: bevy_egui::EguiContexts, mut inter: ResMut<Interactions>) {
+    use bevy_egui::egui;
+    let Ok(ctx) = ctx.ctx_mut() else { return };
+    // only redraw when something's actually changed
+    let mut changed = false;
+    let i = inter.bypass_change_detection();
+    egui::Window::new("interactions")
+        .default_open(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("show instead of follows:");
+                changed |= ui.checkbox(&mut i.shown, egui::Atoms::default()).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("last days:");
+                if ui
+                    .add(egui::DragValue::new(&mut i.days).range(0..=i64::MAX))
+                    .on_hover_text("0 counts everything")
+                    .changed()
+                {
+                    i.dirty = true
+                }
+            });
+            ui.small(format!(
+                "likes only count on everyone's latest {LIKED} posts"
+            ));
+            if i.crawl.seen.is_empty() {
+                return;
+            }
+            ui.label(format!(
+                "fetched {} of {} feeds",
+                i.crawl.fetched + i.crawl.failed,
+                i.crawl.seen.len()
+            ));
+            if i.crawl.failed > 0 {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("{} feeds couldn't be fetched", i.crawl.failed),
+                );
+            }
+            ui.label(format!("{} pairs interacting", i.weights.len()));
+        });
+    if changed {
+        inter.set_changed()
+    }
+}
@@ -0,0 +1,299 @@
+use super::*;
+use atrium_api::com::atproto::repo::list_records;
+use atrium_api::types::TryFromUnknown;
+use atrium_api::types::string::{AtIdentifier, Did};
+
+pub struct Stuff;
+
+impl Plugin for Stuff {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Timeline>()
+            .init_resource::<Lapse>()
+            .add_systems(
+                Update,
+                (fetch, date, play).chain().run_if(in_state(Game::Connect)),
+            )
+            .add_systems(
+                bevy_egui::EguiPrimaryContextPass,
+                lapse.run_if(in_state(Game::Connect)),
+            )
+            .add_systems(OnExit(Game::Connect), clear);
+    }
+}
+
+// who followed who and when
+type Dated = (Did, Did, i64);
+
+#[derive(Resource)]
+struct Lapse {
+    started: bool,
+    crawl: Crawl<Dated>,
+    // kept by did since indices are only looked up when they're dated
+    follows: Vec<Dated>,
+    dirty: bool,
+    playing: bool,
+    // days played per second
+    speed: f32,
+    // saves a screenshot every frame of playback
+    export: bool,
+    frame: usize,
+}
+
+impl Default for Lapse {
+    fn default() -> Self {
+        Self {
+            started: false,
+            crawl: default(),
+            follows: Vec::new(),
+            dirty: false,
+            playing: false,
+            speed: 30.0,
+            export: false,
+            frame: 0,
+        }
+    }
+}
+
+fn clear(mut commands: Commands) {
+    commands.insert_resource(Timeline::default());
+    commands.insert_resource(Lapse::default());
+}
+
+fn fetch(mut lapse: ResMut<Lapse>, users: Query<(Entity, &User)>) {
+    // every follow record is a lot of requests so it waits until it's asked for
+    if !lapse.started {
+        return;
+    }
+    // follows of anyone outside the web are kept in case they join it later
+    let mut follows = lapse.crawl.poll(users.iter(), follows_of);
+    lapse.dirty |= !follows.is_empty();
+    lapse.follows.append(&mut follows);
+}
+
+// the appview doesn't say when a follow happened so it's read from their repo
+async fn follows_of(did: Did) -> Result<Vec<Dated>, String> {
+    let client = atrium_api::client::AtpServiceClient::new(
+        atrium_xrpc_client::reqwest::ReqwestClient::new(auth::pds(&did).await?),
+    );
+    let mut dated = Vec::new();
+    let mut cursor = None;
+    loop {
+        let data = client
+            .service
+            .com
+            .atproto
+            .repo
+            .list_records(
+                list_records::ParametersData {
+                    collection: "app.bsky.graph.follow".parse().unwrap(),
+                    cursor,
+                    limit: 100.try_into().ok(),
+                    repo: AtIdentifier::Did(did.clone()),
+                    reverse: None,
+                }
+                .into(),
+            )
+            .await
+            .map_err(|e| e.to_string())?
+            .data;
+        let empty = data.records.is_empty();
+        for record in data.records {
+            if let Ok(follow) =
+                atrium_api::app::bsky::graph::follow::Record::try_from_unknown(record.data.value)
+            {
+                dated.push((
+                    did.clone(),
+                    follow.data.subject,
+                    follow.data.created_at.as_ref().timestamp(),
+                ))
+            }
+        }
+        cursor = data.cursor;
+        if cursor.is_none() || empty {
+            break;
+        }
+    }
+    Ok(dated)
+}
+
+fn date(
+    mut commands: Commands,
+    mut lapse: ResMut<Lapse>,
+    mut timeline: ResMut<Timeline>,
+    network: Res<Network>,
+    users: Query<&User>,
+) {
+    // someone joining might date follows we already have
+    let joined = network.is_changed() && !lapse.follows.is_empty();
+    if !lapse.dirty && !joined {
+        return;
+    }
+    lapse.dirty = false;
+    let mut links: std::collections::HashMap<_, _> = default();
+    let mut joined: std::collections::HashMap<Entity, i64> = default();
+    for (from, to, when) in &lapse.follows {
+        let (Some(ent), Some(other)) = (network.get(from), network.get(to)) else {
+            continue;
+        };
+        let (Ok(user), Ok(followed)) = (users.get(*ent), users.get(*other)) else {
+            continue;
+        };
+        links.insert((user.index, followed.index), *when);
+        // users show up with their first follow either way
+        for ent in [ent, other] {
+            joined
+                .entry(*ent)
+                .and_modify(|first| *first = (*first).min(*when))
+                .or_insert(*when);
+        }
+    }
+    let mut order: Vec<_> = links.iter().map(|(link, when)| (*when, *link)).collect();
+    order.sort_unstable();
+    // anything that's been dated or undated might've come or gone
+    if timeline.at.is_some() {
+        let relinked = timeline.links.keys().chain(links.keys()).cloned().collect();
+        commands.trigger(Relinked(relinked));
+    }
+    timeline.links = links;
+    timeline.order = order;
+    timeline.users = joined;
+}
+
+fn play(
+    mut commands: Commands,
+    mut lapse: ResMut<Lapse>,
+    mut timeline: ResMut<Timeline>,
+    time: Res<Time>,
+) {
+    if !lapse.playing {
+        return;
+    }
+    let (Some(at), Some((_, end))) = (timeline.at, timeline.span()) else {
+        lapse.playing = false;
+        return;
+    };
+    let next = at + (lapse.speed as f64 * 86400.0 * time.delta_secs_f64()) as i64;
+    timeline.at = Some(next.min(end));
+    if next >= end {
+        lapse.playing = false
+    }
+    // only what was made since last frame needs placing
+    commands.trigger(Relinked(timeline.between(at, next).collect()));
+    if lapse.export {
+        lapse.frame += 1;
+        commands
+            .spawn(bevy::render::view::screenshot::Screenshot::primary_window())
+            .observe(bevy::render::view::screenshot::save_to_disk(format!(
+                "{FRAMES}/{:05}.png",
+                lapse.frame
+            )));
+    }
+}
+
+// on wasm each frame is downloaded instead
+const FRAMES: &str = "frames";
+
+fn date_of(at: i64) -> String {
+    chrono::DateTime::from_timestamp(at, 0)
+        .map(|at| at.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn lapse(
+    mut ctx: bevy_egui::EguiContexts,
+    mut commands: Commands,
+    mut lapse: ResMut<Lapse>,
+    mut timeline: ResMut<Timeline>,
+    progress: Res<Progress>,
+) {
+    use bevy_egui::egui;
+    let Ok(ctx) = ctx.ctx_mut() else { return };
+    egui::Window::new("time-lapse")
+        .default_open(false)
+        .show(ctx, |ui| {
+            if !lapse.started {
+                ui.label("reads when every follow was made from everyone's repo");
+                // so everyone's there to be fetched
+                let crawled = !progress.fetching && progress.crawling == 0;
+                if ui
+                    .add_enabled(crawled, egui::Button::new("fetch follow dates"))
+                    .on_disabled_hover_text("waits for the web to finish crawling")
+                    .clicked()
+                {
+                    lapse.started = true
+                }
+                return;
+            }
+            ui.label(format!(
+                "fetched {} of {} users' follows",
+                lapse.crawl.fetched + lapse.crawl.failed,
+                lapse.crawl.seen.len()
+            ));
+            if lapse.crawl.failed > 0 {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("{} users' follows couldn't be fetched", lapse.crawl.failed),
+                );
+            }
+            let Some((start, end)) = timeline.span() else {
+                return;
+            };
+            let mut on = timeline.at.is_some();
+            ui.horizontal(|ui| {
+                ui.label("play back:");
+                if ui.checkbox(&mut on, egui::Atoms::default()).changed() {
+                    timeline.at = on.then_some(start);
+                    lapse.playing = false;
+                    commands.trigger(Relinked(timeline.between(start, end).collect()))
+                }
+            });
+            let Some(mut at) = timeline.at else {
+                return;
+            };
+            ui.horizontal(|ui| {
+                if ui
+                    .button(match lapse.playing {
+                        true => "pause",
+                        false => "play",
+                    })
+                    .clicked()
+                {
+                    // starting again from the end goes back to the start
+                    if !lapse.playing && at >= end {
+                        at = start
+                    }
+                    lapse.playing = !lapse.playing
+                }
+                if ui
+                    .add(
+                        egui::Slider::new(&mut at, start..=end)
+                            .custom_formatter(|at, _| date_of(at as i64)),
+                    )
+                    .changed()
+                {
+                    lapse.playing = false
+                }
+            });
+            if let Some(was) = timeline.at.filter(|was| *was != at) {
+                timeline.at = Some(at);
+                commands.trigger(Relinked(timeline.between(was, at).collect()))
+            }
+            ui.horizontal(|ui| {
+                ui.label("days per second:");
+                ui.add(egui::DragValue::new(&mut lapse.speed).range(0.1..=f32::MAX));
+            });
+            ui.horizontal(|ui| {
+                ui.label("export frames:");
+                if ui
+                    .checkbox(&mut lapse.export, egui::Atoms::default())
+                    .on_hover_text(format!("saved to {FRAMES}/ while playing"))
+                    .changed()
+                    && lapse.export
+                {
+                    #[cfg(not(target_family = "wasm"))]
+                    let _ = std::fs::create_dir_all(FRAMES);
+                    lapse.frame = 0;
+                }
+            });
+        });
+}
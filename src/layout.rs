@@ -0,0 +1,472 @@
+// a force directed layout along the same lines as d3-force
+// nodes, links and pins are changed in place so the web can grow without starting over
+use std::collections::HashMap;
+
+// same as d3's defaults
+const ALPHA_MIN: f64 = 0.001;
+const VELOCITY_DECAY: f64 = 0.6;
+const THETA2: f64 = 0.9 * 0.9;
+const DISTANCE_MIN2: f64 = 1.0;
+// what alpha gets topped back up to when anything changes
+const REHEAT: f64 = 0.3;
+const NONE: u32 = u32::MAX;
+
+pub enum Change {
+    // a new node at the end
+    Add([f64; 2]),
+    // holds a node somewhere or lets it go
+    Pin(usize, Option<[f64; 2]>),
+    // leaves a node where it is and out of the charge
+    Freeze(usize, bool),
+    Link(usize, usize),
+    Unlink(usize, usize),
+    Charge(f64),
+    Distance(f64),
+}
+
+#[derive(Clone, Default)]
+struct Node {
+    x: f64,
+    y: f64,
+    vx: f64,
+    vy: f64,
+    fixed: Option<[f64; 2]>,
+    frozen: bool,
+}
+
+// a square of the barnes-hut quadtree
+#[derive(Clone, Copy)]
+struct Quad {
+    // 0 for none since the root is nobody's child
+    kids: [u32; 4],
+    // the first node in a leaf with any at the same spot chained on through next
+    node: u32,
+    // centre of charge
+    x: f64,
+    y: f64,
+    strength: f64,
+}
+
+impl Quad {
+    fn leaf(node: u32) -> Self {
+        Self {
+            kids: [0; 4],
+            node,
+            x: 0.0,
+            y: 0.0,
+            strength: 0.0,
+        }
+    }
+
+    fn internal(&self) -> bool {
+        self.node == NONE && self.kids != [0; 4]
+    }
+}
+
+pub struct Layout {
+    alpha: f64,
+    decay: f64,
+    charge: f64,
+    distance: f64,
+    nodes: Vec<Node>,
+    links: Vec<(usize, usize)>,
+    // where each link is in links so it can be taken out without a search
+    slots: HashMap<(usize, usize), usize>,
+    // links per node which decides how hard each link pulls
+    count: Vec<usize>,
+    seed: u32,
+    // kept between ticks so they aren't reallocated
+    tree: Vec<Quad>,
+    next: Vec<u32>,
+}
+
+impl Layout {
+    pub fn new(charge: f64, distance: f64) -> Self {
+        Self {
+            alpha: 1.0,
+            decay: 1.0 - ALPHA_MIN.powf(1.0 / 300.0),
+            charge,
+            distance,
+            nodes: Vec::new(),
+            links: Vec::new(),
+            slots: HashMap::new(),
+            count: Vec::new(),
+            seed: 1,
+            tree: Vec::new(),
+            next: Vec::new(),
+        }
+    }
+
+    pub fn apply(&mut self, change: Change) {
+        match change {
+            Change::Add([x, y]) => {
+                self.nodes.push(Node {
+                    x,
+                    y,
+                    ..Node::default()
+                });
+                self.count.push(0);
+            }
+            Change::Pin(i, at) => {
+                let node = &mut self.nodes[i];
+                node.fixed = at;
+                if let Some([x, y]) = at {
+                    (node.x, node.y) = (x, y)
+                }
+            }
+            Change::Freeze(i, frozen) => {
+                let node = &mut self.nodes[i];
+                node.frozen = frozen;
+                (node.vx, node.vy) = (0.0, 0.0);
+            }
+            Change::Link(from, to) => {
+                if from == to || self.slots.contains_key(&(from, to)) {
+                    return;
+                }
+                self.slots.insert((from, to), self.links.len());
+                self.links.push((from, to));
+                self.count[from] += 1;
+                self.count[to] += 1;
+            }
+            Change::Unlink(from, to) => {
+                let Some(slot) = self.slots.remove(&(from, to)) else {
+                    return;
+                };
+                self.links.swap_remove(slot);
+                if let Some(moved) = self.links.get(slot) {
+                    self.slots.insert(*moved, slot);
+                }
+                self.count[from] -= 1;
+                self.count[to] -= 1;
+            }
+            Change::Charge(charge) => self.charge = charge,
+            Change::Distance(distance) => self.distance = distance,
+        }
+        // reheat rather than restart so the layout doesn't explode on every change
+        self.alpha = self.alpha.max(REHEAT);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.alpha <= ALPHA_MIN
+    }
+
+    pub fn position(&self, i: usize) -> Option<[f64; 2]> {
+        self.nodes.get(i).map(|node| [node.x, node.y])
+    }
+
+    pub fn tick(&mut self, iterations: usize) {
+        for _ in 0..iterations {
+            self.alpha -= self.alpha * self.decay;
+            self.link();
+            self.repel();
+            self.centre();
+            for node in &mut self.nodes {
+                if node.frozen {
+                    continue;
+                }
+                if let Some([x, y]) = node.fixed {
+                    (node.x, node.y, node.vx, node.vy) = (x, y, 0.0, 0.0);
+                    continue;
+                }
+                // stops anything flung far enough to be useless
+                node.x += node.vx.clamp(i32::MIN as f64, i32::MAX as f64);
+                node.y += node.vy.clamp(i32::MIN as f64, i32::MAX as f64);
+                node.vx *= VELOCITY_DECAY;
+                node.vy *= VELOCITY_DECAY;
+            }
+        }
+    }
+
+    // a tiny nudge so nodes on top of each other can push apart
+    fn jiggle(&mut self) -> f64 {
+        self.seed = self.seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        (self.seed as f64 / 4294967296.0 - 0.5) * 1e-6
+    }
+
+    fn link(&mut self) {
+        for i in 0..self.links.len() {
+            let (from, to) = self.links[i];
+            let (s, t) = (&self.nodes[from], &self.nodes[to]);
+            let mut x = t.x + t.vx - s.x - s.vx;
+            let mut y = t.y + t.vy - s.y - s.vy;
+            if x == 0.0 {
+                x = self.jiggle()
+            }
+            if y == 0.0 {
+                y = self.jiggle()
+            }
+            let (cs, ct) = (self.count[from] as f64, self.count[to] as f64);
+            // the less connected end moves more
+            let strength = 1.0 / cs.min(ct);
+            let bias = cs / (cs + ct);
+            let l = x.hypot(y);
+            let l = (l - self.distance) / l * self.alpha * strength;
+            let t = &mut self.nodes[to];
+            t.vx -= x * l * bias;
+            t.vy -= y * l * bias;
+            let s = &mut self.nodes[from];
+            s.vx += x * l * (1.0 - bias);
+            s.vy += y * l * (1.0 - bias);
+        }
+    }
+
+    // barnes-hut so far away groups of nodes push as one
+    fn repel(&mut self) {
+        let live = || {
+            self.nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| !node.frozen)
+        };
+        let (mut x0, mut y0, mut x1, mut y1) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for (_, node) in live() {
+            (x0, y0) = (x0.min(node.x), y0.min(node.y));
+            (x1, y1) = (x1.max(node.x), y1.max(node.y));
+        }
+        if x0 > x1 || self.charge == 0.0 {
+            return;
+        }
+        let size = (x1 - x0).max(y1 - y0).max(1.0);
+        self.tree.clear();
+        self.tree.push(Quad::leaf(NONE));
+        self.next.clear();
+        self.next.resize(self.nodes.len(), NONE);
+        for i in 0..self.nodes.len() {
+            if !self.nodes[i].frozen {
+                self.insert(i as u32, [x0, y0], size)
+            }
+        }
+        // children always come after their parent so backwards is bottom up
+        for q in (0..self.tree.len()).rev() {
+            let quad = self.tree[q];
+            if quad.node != NONE {
+                let node = &self.nodes[quad.node as usize];
+                let mut count = 0.0;
+                let mut at = quad.node;
+                while at != NONE {
+                    count += 1.0;
+                    at = self.next[at as usize];
+                }
+                self.tree[q] = Quad {
+                    x: node.x,
+                    y: node.y,
+                    strength: self.charge * count,
+                    ..quad
+                };
+                continue;
+            }
+            let (mut x, mut y, mut weight, mut strength) = (0.0, 0.0, 0.0, 0.0);
+            for kid in quad.kids.into_iter().filter(|kid| *kid != 0) {
+                let kid = self.tree[kid as usize];
+                let c = kid.strength.abs();
+                strength += kid.strength;
+                weight += c;
+                x += c * kid.x;
+                y += c * kid.y;
+            }
+            if weight > 0.0 {
+                self.tree[q] = Quad {
+                    x: x / weight,
+                    y: y / weight,
+                    strength,
+                    ..quad
+                };
+            }
+        }
+        let mut stack = Vec::new();
+        for i in 0..self.nodes.len() {
+            let node = &self.nodes[i];
+            if node.frozen || node.fixed.is_some() {
+                continue;
+            }
+            let (nx, ny) = (node.x, node.y);
+            let (mut vx, mut vy) = (0.0, 0.0);
+            stack.push((0, x0, y0, size));
+            while let Some((q, qx, qy, w)) = stack.pop() {
+                let quad = self.tree[q as usize];
+                if quad.strength == 0.0 {
+                    continue;
+                }
+                let (mut x, mut y) = (quad.x - nx, quad.y - ny);
+                let mut l = x * x + y * y;
+                let internal = quad.internal();
+                // far enough away to count as one
+                if w * w / THETA2 < l
+                    || !internal && (quad.node != i as u32 || self.next[i] != NONE)
+                {
+                    if x == 0.0 {
+                        x = self.jiggle();
+                        l += x * x;
+                    }
+                    if y == 0.0 {
+                        y = self.jiggle();
+                        l += y * y;
+                    }
+                    if l < DISTANCE_MIN2 {
+                        l = (DISTANCE_MIN2 * l).sqrt()
+                    }
+                }
+                if internal && w * w / THETA2 >= l {
+                    let half = w / 2.0;
+                    for (k, kid) in quad.kids.into_iter().enumerate() {
+                        if kid != 0 {
+                            let (dx, dy) = ((k & 1) as f64, (k >> 1) as f64);
+                            stack.push((kid, qx + dx * half, qy + dy * half, half));
+                        }
+                    }
+                    continue;
+                }
+                if internal {
+                    vx += x * quad.strength * self.alpha / l;
+                    vy += y * quad.strength * self.alpha / l;
+                    continue;
+                }
+                // a leaf close by pushes with each of its nodes except this one
+                let mut at = quad.node;
+                while at != NONE {
+                    if at != i as u32 {
+                        let w = self.charge * self.alpha / l;
+                        vx += x * w;
+                        vy += y * w;
+                    }
+                    at = self.next[at as usize];
+                }
+            }
+            let node = &mut self.nodes[i];
+            node.vx += vx;
+            node.vy += vy;
+        }
+    }
+
+    fn insert(&mut self, i: u32, [mut x0, mut y0]: [f64; 2], mut size: f64) {
+        let node = &self.nodes[i as usize];
+        let (x, y) = (node.x, node.y);
+        let mut q = 0;
+        loop {
+            let quad = self.tree[q];
+            let half = size / 2.0;
+            let quadrant = |x: f64, y: f64, x0: f64, y0: f64| {
+                (x >= x0 + half) as usize | ((y >= y0 + half) as usize) << 1
+            };
+            if quad.internal() {
+                let k = quadrant(x, y, x0, y0);
+                x0 += (k & 1) as f64 * half;
+                y0 += (k >> 1) as f64 * half;
+                size = half;
+                if quad.kids[k] == 0 {
+                    self.tree[q].kids[k] = self.tree.len() as u32;
+                    self.tree.push(Quad::leaf(i));
+                    return;
+                }
+                q = quad.kids[k] as usize;
+                continue;
+            }
+            if quad.node == NONE {
+                self.tree[q].node = i;
+                return;
+            }
+            let other = &self.nodes[quad.node as usize];
+            // same spot or too deep to tell apart so they share the leaf
+            if other.x == x && other.y == y || size < 1e-6 {
+                self.next[i as usize] = self.next[quad.node as usize];
+                self.next[quad.node as usize] = i;
+                return;
+            }
+            let k = quadrant(other.x, other.y, x0, y0);
+            self.tree[q].node = NONE;
+            self.tree[q].kids[k] = self.tree.len() as u32;
+            self.tree.push(Quad::leaf(quad.node));
+        }
+    }
+
+    fn centre(&mut self) {
+        let (mut sx, mut sy, mut n) = (0.0, 0.0, 0.0);
+        for node in self.nodes.iter().filter(|node| !node.frozen) {
+            sx += node.x;
+            sy += node.y;
+            n += 1.0;
+        }
+        if n == 0.0 {
+            return;
+        }
+        let (sx, sy) = (sx / n, sy / n);
+        for node in self.nodes.iter_mut().filter(|node| !node.frozen) {
+            node.x -= sx;
+            node.y -= sy;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(positions: &[[f64; 2]]) -> Layout {
+        let mut layout = Layout::new(-30.0, 30.0);
+        for pos in positions {
+            layout.apply(Change::Add(*pos))
+        }
+        layout
+    }
+
+    fn apart(layout: &Layout, i1: usize, i2: usize) -> f64 {
+        let ([x1, y1], [x2, y2]) = (layout.position(i1).unwrap(), layout.position(i2).unwrap());
+        (x2 - x1).hypot(y2 - y1)
+    }
+
+    #[test]
+    fn unlink() {
+        let mut layout = layout(&[[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]]);
+        layout.apply(Change::Link(0, 1));
+        layout.apply(Change::Link(1, 2));
+        // linking twice doesn't count twice
+        layout.apply(Change::Link(1, 2));
+        layout.apply(Change::Unlink(0, 1));
+        layout.apply(Change::Unlink(0, 1));
+        assert_eq!(layout.links, [(1, 2)]);
+        assert_eq!(layout.slots[&(1, 2)], 0);
+        assert_eq!(layout.count, [0, 1, 1]);
+    }
+
+    #[test]
+    fn settles() {
+        let mut layout = layout(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [200.0, 0.0]]);
+        layout.apply(Change::Link(0, 3));
+        layout.tick(300);
+        assert!(layout.is_finished());
+        // the link pulls them in and everyone else is pushed apart
+        assert!(apart(&layout, 0, 3) < 100.0);
+        assert!(apart(&layout, 1, 2) > 10.0);
+    }
+
+    #[test]
+    fn pinned() {
+        let mut layout = layout(&[[0.0, 0.0], [1.0, 1.0]]);
+        layout.apply(Change::Pin(0, Some([5.0, 5.0])));
+        layout.apply(Change::Freeze(1, true));
+        layout.tick(50);
+        assert_eq!(layout.position(0), Some([5.0, 5.0]));
+        assert_eq!(layout.position(1), Some([1.0, 1.0]));
+        layout.apply(Change::Pin(0, None));
+        layout.apply(Change::Freeze(1, false));
+        layout.tick(50);
+        assert_ne!(layout.position(1), Some([1.0, 1.0]));
+    }
+
+    #[test]
+    fn stacked() {
+        // nodes on top of each other still get pushed apart
+        let mut layout = layout(&[[0.0, 0.0]; 5]);
+        layout.tick(100);
+        assert!(apart(&layout, 0, 1) > 1.0);
+    }
+
+    #[test]
+    fn reheat() {
+        let mut layout = layout(&[[0.0, 0.0], [1.0, 0.0]]);
+        layout.tick(1000);
+        assert!(layout.is_finished());
+        layout.apply(Change::Charge(-60.0));
+        assert!(!layout.is_finished());
+    }
+}
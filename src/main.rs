@@ -5,13 +5,23 @@ use bevy::prelude::*;
 use bevy_dylib;
 
 mod ask;
+mod auth;
 mod avatar;
 mod bsky;
+mod bundle;
 mod compat;
 use compat::*;
 mod camera;
+mod compare;
 mod config;
 mod connect;
+mod filter;
+mod interact;
+mod lapse;
+mod layout;
+mod panel;
+mod progress;
+mod search;
 
 fn main() -> AppExit {
     bevy::app::App::new()
@@ -35,11 +45,21 @@ fn main() -> AppExit {
                 }),
             MeshPickingPlugin,
             bevy_egui::EguiPlugin::default(),
-            ask::Stuff,
-            bsky::Stuff,
-            connect::Stuff,
-            config::Stuff,
-            camera::Stuff,
+            (
+                ask::Stuff,
+                auth::Stuff,
+                bsky::Stuff,
+                connect::Stuff,
+                config::Stuff,
+                camera::Stuff,
+                search::Stuff,
+                panel::Stuff,
+                filter::Stuff,
+                progress::Stuff,
+                compare::Stuff,
+                interact::Stuff,
+                lapse::Stuff,
+            ),
         ))
         .init_state::<Game>()
         .add_systems(
@@ -57,13 +77,9 @@ fn main() -> AppExit {
         .run()
 }
 
-static CLIENT: std::sync::LazyLock<
-    atrium_api::client::AtpServiceClient<atrium_xrpc_client::reqwest::ReqwestClient>,
-> = std::sync::LazyLock::new(|| {
-    atrium_api::client::AtpServiceClient::new(atrium_xrpc_client::reqwest::ReqwestClient::new(
-        "https://public.api.bsky.app",
-    ))
-});
+// goes through whoever's logged in if anyone is
+static CLIENT: std::sync::LazyLock<atrium_api::client::AtpServiceClient<auth::Client>> =
+    std::sync::LazyLock::new(|| atrium_api::client::AtpServiceClient::new(auth::Client));
 
 #[derive(Resource, Reflect)]
 struct Config {
@@ -72,6 +88,24 @@ struct Config {
     charge: f64,
     link: f64,
     size: f32,
+    pin: bool,
+    directed: bool,
+    bundle: bool,
+    bundling: f32,
+    // whether the roots stay put or get pulled around like everyone else
+    anchored: bool,
+    // how many users always have their name shown
+    labelled: usize,
+    rank: Rank,
+}
+
+// what decides who gets a permanent label
+#[derive(Reflect, Default, PartialEq, Clone, Copy)]
+enum Rank {
+    #[default]
+    Followed,
+    Follows,
+    Degree,
 }
 
 impl Default for Config {
@@ -82,12 +116,39 @@ impl Default for Config {
             charge: -30.0,
             link: 30.0,
             size: 6.0,
+            pin: false,
+            directed: false,
+            bundle: false,
+            bundling: 0.8,
+            anchored: true,
+            labelled: 0,
+            rank: Rank::Followed,
         }
     }
 }
 
+// users who might've been hidden or shown
+#[derive(Event)]
+struct Toggled(Vec<Entity>);
+
+// follows that might've come or gone with the time-lapse
+#[derive(Event)]
+struct Relinked(Vec<(usize, usize)>);
+
 #[derive(Event)]
-struct Rebuild;
+struct Unpin;
+
+// throws the web away and goes back to asking for a handle
+#[derive(Event)]
+struct Reset;
+
+// frames every user
+#[derive(Event)]
+struct Fit;
+
+// centres the camera on a user
+#[derive(Event)]
+struct Focus;
 
 #[derive(Resource, Deref)]
 struct Orb(Handle<Mesh>);
@@ -95,39 +156,446 @@ struct Orb(Handle<Mesh>);
 #[derive(Resource, Deref)]
 struct Lines(Handle<Mesh>);
 
-#[derive(Resource, Deref, DerefMut)]
+// the hovered user's and the selection's follows drawn over everything else
+#[derive(Resource, Deref)]
+struct Glow(Handle<Mesh>);
+
+// what the line mesh is built from
+#[derive(Resource, Default)]
+struct Strands {
+    // by user index
+    colours: Vec<[f32; 4]>,
+    // visible follows and whether they're mutual
+    links: Vec<(usize, usize, bool)>,
+    // where each visible follow is in links so it can be taken out without a search
+    at: std::collections::HashMap<(usize, usize), usize>,
+}
+
+// bundled polylines for each of the strands' links
+#[derive(Resource, Default)]
+struct Bundle {
+    task: Option<bevy::tasks::Task<Vec<Vec<Vec2>>>>,
+    lines: Vec<Vec<Vec2>>,
+    // false once anything's moved since they were bundled
+    fresh: bool,
+    // what the mesh was last drawn with
+    shown: (bool, f32),
+}
+
+#[derive(Resource, Default)]
 struct Sim {
-    #[deref]
-    sim: fjadra::Simulation,
-    nodes: Vec<fjadra::Node>,
+    // taken by the task while it's ticking on the compute pool
+    layout: Option<layout::Layout>,
+    task: Option<bevy::tasks::Task<layout::Layout>>,
+    // changes made while the layout was away ticking
+    pending: Vec<layout::Change>,
+    // every follow in the web by the user indices at either end
     links: Vec<(usize, usize)>,
+    // where each follow is in links
+    index: std::collections::HashMap<(usize, usize), usize>,
+    // whether each follow is pulling in the layout
+    laid: Vec<bool>,
+    // each user's follows and followers by where they are in links
+    near: Vec<Vec<usize>>,
+    // follows that haven't been placed yet
+    fresh: Vec<usize>,
+    // users left out of the line mesh and the ones left out of the layout too
+    hid: Vec<bool>,
+    out: Vec<bool>,
 }
 
-#[derive(Resource, Deref)]
+// how far along fetching everyone's follows is
+#[derive(Resource, Default)]
+struct Progress {
+    // still paging through the roots' own follows
+    fetching: bool,
+    pages: usize,
+    // follows listed so far across all the roots
+    listed: usize,
+    // successful requests so far
+    requests: usize,
+    // users whose follows are still being fetched
+    crawling: usize,
+    done: usize,
+    // users we gave up on after too many errors
+    failed: usize,
+    // roots we gave up paging through
+    cut: usize,
+    // seconds since startup when fetching started
+    since: f32,
+}
+
+#[derive(Deref)]
 struct Profile {
     actor: atrium_api::types::string::AtIdentifier,
     #[deref]
     profile: atrium_api::app::bsky::actor::defs::ProfileViewDetailedData,
 }
 
-#[derive(Component)]
+// everyone whose follows the web is built from with you first
+#[derive(Resource, Deref, DerefMut, Default)]
+struct Roots(Vec<Profile>);
+
+impl Roots {
+    fn ents<'a>(&'a self, network: &'a Network) -> impl Iterator<Item = Entity> + 'a {
+        self.iter()
+            .filter_map(|root| network.get(&root.did).cloned())
+    }
+}
+
+// when follows were made so the web can be played back in the order it grew
+#[derive(Resource, Default)]
+struct Timeline {
+    // by the user indices at either end
+    links: std::collections::HashMap<(usize, usize), i64>,
+    // the dated links from first to last
+    order: Vec<(i64, (usize, usize))>,
+    // when each user first shows up
+    users: std::collections::HashMap<Entity, i64>,
+    // how far playback has got if it's on
+    at: Option<i64>,
+}
+
+// anything without a date is always there
+impl Timeline {
+    fn linked(&self, i1: usize, i2: usize) -> bool {
+        self.at
+            .is_none_or(|at| self.links.get(&(i1, i2)).is_none_or(|when| *when <= at))
+    }
+
+    fn joined(&self, ent: Entity) -> bool {
+        self.at
+            .is_none_or(|at| self.users.get(&ent).is_none_or(|when| *when <= at))
+    }
+
+    // when the first and last dated links were made
+    fn span(&self) -> Option<(i64, i64)> {
+        Some((self.order.first()?.0, self.order.last()?.0))
+    }
+
+    // links made after one time up to another
+    fn between(&self, from: i64, to: i64) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let (from, to) = (from.min(to), from.max(to));
+        let start = self.order.partition_point(|(when, _)| *when <= from);
+        let end = self.order.partition_point(|(when, _)| *when <= to);
+        self.order[start..end].iter().map(|(_, link)| *link)
+    }
+}
+
+// something fetched for everyone in the web, a few at a time so we don't get rate limited
+struct Crawl<T> {
+    // users who've been queued
+    seen: std::collections::HashSet<Entity>,
+    queue: Vec<atrium_api::types::string::Did>,
+    tasks: Vec<bevy::tasks::Task<Result<Vec<T>, String>>>,
+    fetched: usize,
+    failed: usize,
+}
+
+impl<T> Default for Crawl<T> {
+    fn default() -> Self {
+        Self {
+            seen: default(),
+            queue: Vec::new(),
+            tasks: Vec::new(),
+            fetched: 0,
+            failed: 0,
+        }
+    }
+}
+
+impl<T: Send + 'static> Crawl<T> {
+    // queues anyone new, hands back whatever's come in and starts the next few
+    fn poll<'a, F>(
+        &mut self,
+        users: impl Iterator<Item = (Entity, &'a User)>,
+        fetch: impl Fn(atrium_api::types::string::Did) -> F,
+    ) -> Vec<T>
+    where
+        F: Future<Output = Result<Vec<T>, String>> + Send + 'static,
+    {
+        for (ent, user) in users {
+            if self.seen.insert(ent) {
+                self.queue.push(user.did.clone())
+            }
+        }
+        let mut found = Vec::new();
+        let (mut fetched, mut failed) = (0, 0);
+        self.tasks.retain_mut(
+            |task| match bevy::tasks::block_on(bevy::tasks::poll_once(task)) {
+                Some(Ok(more)) => {
+                    found.extend(more);
+                    fetched += 1;
+                    false
+                }
+                Some(Err(_)) => {
+                    failed += 1;
+                    false
+                }
+                None => true,
+            },
+        );
+        self.fetched += fetched;
+        self.failed += failed;
+        while self.tasks.len() < 4
+            && let Some(did) = self.queue.pop()
+        {
+            self.tasks
+                .push(bevy::tasks::IoTaskPool::get().spawn(Compat::new(fetch(did))))
+        }
+        found
+    }
+}
+
+// list or starter pack members that go straight into the web instead of being roots
+#[derive(Resource, Deref, Default)]
+struct Members(Vec<atrium_api::app::bsky::actor::defs::ProfileViewData>);
+
+// roots are spread out evenly so their follows don't all pile up in the middle
+fn home(i: usize, n: usize) -> Vec2 {
+    if n < 2 {
+        return Vec2::ZERO;
+    }
+    let angle = std::f32::consts::TAU * i as f32 / n as f32 + std::f32::consts::PI;
+    // bigger teams get a bigger circle so they aren't on top of each other
+    Vec2::from_angle(angle) * 200.0 * (n as f32 / 2.0).sqrt()
+}
+
+#[derive(Component, Deref)]
 struct User {
-    handle: String,
+    // everything we were told about them, keyed by did since handles can change
+    #[deref]
+    profile: atrium_api::app::bsky::actor::defs::ProfileViewData,
     shared: Vec<Entity>,
+    // users in the web that follow them
+    fans: Vec<Entity>,
     index: usize,
+    // only fetched once a follower count filter is set
+    followers: Option<i64>,
+    community: Option<usize>,
+}
+
+impl User {
+    fn name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or_default()
+    }
+
+    fn labels(&self) -> impl Iterator<Item = &str> {
+        self.labels.iter().flatten().map(|label| label.val.as_str())
+    }
 }
 
+// overrides wherever the simulation would put a user
+#[derive(Component, Deref, DerefMut)]
+struct Pinned(Vec2);
+
+// marks a user being dragged so letting go doesn't count as a click
+#[derive(Component)]
+struct Grab;
+
+// what the camera scale is easing towards
+#[derive(Resource)]
+struct Zoom {
+    scale: f32,
+    // screen position that should stay put while zooming
+    anchor: Option<Vec2>,
+}
+
+// where the camera is easing towards if anywhere
+#[derive(Resource, Deref, DerefMut, Default)]
+struct Glide(Option<Vec2>);
+
+// saved camera positions and scales
+#[derive(Resource, Deref, DerefMut, Default)]
+struct Bookmarks(Vec<(Vec2, f32)>);
+
 #[derive(Resource, Deref, DerefMut, Default)]
 struct Network {
     #[deref]
-    map: std::collections::BTreeMap<String, Entity>,
+    map: std::collections::HashMap<atrium_api::types::string::Did, Entity>,
     max: usize,
 }
 
+// the last selected user's profile is open in the side panel
+#[derive(Resource, Deref, DerefMut, Default)]
+struct Selected(Vec<Entity>);
+
+// the selected users and the neighbourhood they all share
+#[derive(Resource, Default)]
+struct Ego {
+    users: std::collections::HashSet<Entity>,
+    hide: bool,
+}
+
+// the shortest path found between two selected users
+#[derive(Resource, Default)]
+struct Route {
+    directed: bool,
+    ends: Option<(Entity, Entity)>,
+    // none if they aren't connected
+    path: Option<Vec<Entity>>,
+}
+
+// criteria a user has to meet to stay visible
+#[derive(Resource)]
+struct Filter {
+    degree: usize,
+    mutuals: bool,
+    followers: (i64, i64),
+    // in days
+    age: i64,
+    avatar: bool,
+    // comma separated label values to hide
+    labels: String,
+    community: Option<usize>,
+    // leave hidden users out of the layout too
+    layout: bool,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            degree: 0,
+            mutuals: false,
+            followers: (0, i64::MAX),
+            age: 0,
+            avatar: false,
+            labels: String::new(),
+            community: None,
+            layout: false,
+        }
+    }
+}
+
+impl Filter {
+    // whether anyone could be left out at all
+    fn active(&self) -> bool {
+        let default = Self::default();
+        self.degree > default.degree
+            || self.mutuals
+            || self.followers != default.followers
+            || self.age > default.age
+            || self.avatar
+            || !self.labels.trim().is_empty()
+            || self.community.is_some()
+    }
+}
+
+// users that don't make it through the filter
+#[derive(Resource, Deref, DerefMut, Default)]
+struct Hidden(std::collections::HashSet<Entity>);
+
+// sizes of the detected communities where the index is the id
+#[derive(Resource, Deref, DerefMut, Default)]
+struct Communities(Vec<usize>);
+
+#[derive(Event)]
+struct Detect;
+
+// users matching the query in order of relevance
+#[derive(Resource, Default)]
+struct Search {
+    query: String,
+    matches: Vec<Entity>,
+}
+
 #[derive(States, Default, Debug, Eq, PartialEq, Hash, Clone)]
 enum Game {
     #[default]
     Ask,
-    Get,
     Connect,
 }
+
+// someone with nothing but a place in the web
+#[cfg(test)]
+fn dummy(index: usize) -> User {
+    User {
+        profile: atrium_api::app::bsky::actor::defs::ProfileViewData {
+            associated: None,
+            avatar: None,
+            created_at: None,
+            description: None,
+            did: format!("did:plc:dummy{index}").parse().unwrap(),
+            display_name: None,
+            handle: format!("dummy{index}.bsky.social").parse().unwrap(),
+            indexed_at: None,
+            labels: None,
+            pronouns: None,
+            status: None,
+            verification: None,
+            viewer: None,
+        },
+        shared: Vec::new(),
+        fans: Vec::new(),
+        index,
+        followers: None,
+        community: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeline() -> Timeline {
+        let links: std::collections::HashMap<_, _> =
+            [((0, 1), 10), ((1, 2), 20), ((2, 0), 20), ((0, 3), 30)].into();
+        let mut order: Vec<_> = links.iter().map(|(link, when)| (*when, *link)).collect();
+        order.sort_unstable();
+        Timeline {
+            links,
+            order,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn linked() {
+        let mut timeline = timeline();
+        // everything's there when it's not playing back
+        assert!(timeline.linked(0, 3));
+        timeline.at = Some(20);
+        assert!(timeline.linked(0, 1));
+        assert!(timeline.linked(1, 2));
+        assert!(!timeline.linked(0, 3));
+        // only the way it was made has a date
+        assert!(timeline.linked(3, 0));
+        assert!(timeline.linked(4, 5));
+    }
+
+    #[test]
+    fn joined() {
+        let mut world = World::new();
+        let (early, late, undated) = (
+            world.spawn_empty().id(),
+            world.spawn_empty().id(),
+            world.spawn_empty().id(),
+        );
+        let mut timeline = timeline();
+        timeline.users = [(early, 10), (late, 30)].into();
+        assert!(timeline.joined(late));
+        timeline.at = Some(15);
+        assert!(timeline.joined(early));
+        assert!(!timeline.joined(late));
+        assert!(timeline.joined(undated));
+    }
+
+    #[test]
+    fn between() {
+        let timeline = timeline();
+        let between = |from, to| {
+            let mut links: Vec<_> = timeline.between(from, to).collect();
+            links.sort_unstable();
+            links
+        };
+        // the start's left out and the end's kept in
+        assert_eq!(between(10, 20), vec![(1, 2), (2, 0)]);
+        assert_eq!(between(0, 10), vec![(0, 1)]);
+        // going backwards covers the same links
+        assert_eq!(between(30, 10), between(10, 30));
+        assert!(between(20, 20).is_empty());
+        assert!(between(31, 100).is_empty());
+        assert_eq!(timeline.span(), Some((10, 30)));
+        assert_eq!(Timeline::default().span(), None);
+    }
+}
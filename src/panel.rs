@@ -0,0 +1,466 @@
+use super::*;
+use atrium_api::app::bsky::actor::get_profile;
+
+pub struct Stuff;
+
+impl Plugin for Stuff {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Selected>()
+            .init_resource::<Ego>()
+            .init_resource::<Route>()
+            .init_resource::<Card>()
+            .add_systems(
+                Update,
+                (
+                    (fetch, ego, forget).run_if(resource_changed::<Selected>),
+                    walk.run_if(
+                        resource_changed::<Route>
+                            .or(resource_changed::<Hidden>)
+                            .or(resource_changed::<Timeline>),
+                    ),
+                    check,
+                )
+                    .chain()
+                    .run_if(in_state(Game::Connect)),
+            )
+            .add_systems(
+                bevy_egui::EguiPrimaryContextPass,
+                panel.run_if(in_state(Game::Connect)),
+            )
+            .add_systems(OnExit(Game::Connect), clear);
+    }
+}
+
+#[derive(Resource, Default)]
+struct Card {
+    ent: Option<Entity>,
+    task: Option<
+        bevy::tasks::Task<atrium_api::xrpc::Result<get_profile::Output, get_profile::Error>>,
+    >,
+    profile: Option<atrium_api::app::bsky::actor::defs::ProfileViewDetailedData>,
+    err: Option<String>,
+    follows: Vec<Entity>,
+    followers: Vec<Entity>,
+    mutuals: Vec<Entity>,
+}
+
+fn clear(mut commands: Commands) {
+    commands.insert_resource(Selected::default());
+    commands.insert_resource(Ego::default());
+    commands.insert_resource(Route::default());
+    commands.insert_resource(Card::default());
+}
+
+fn fetch(
+    selected: Res<Selected>,
+    mut card: ResMut<Card>,
+    roots: Res<Roots>,
+    network: Res<Network>,
+    users: Query<(Entity, &User)>,
+) {
+    let last = selected.last().cloned();
+    if card.ent == last {
+        return;
+    }
+    *card = Card {
+        ent: last,
+        ..default()
+    };
+    let Some((_, user)) = last.and_then(|ent| users.get(ent).ok()) else {
+        return;
+    };
+    let actor = atrium_api::types::string::AtIdentifier::Did(user.did.clone());
+    card.task = Some(
+        bevy::tasks::IoTaskPool::get().spawn(Compat::new(
+            CLIENT
+                .service
+                .app
+                .bsky
+                .actor
+                .get_profile(get_profile::ParametersData { actor }.into()),
+        )),
+    );
+    card.follows = user.shared.clone();
+    card.followers = user.fans.clone();
+    let mutuals = |user: &User| -> std::collections::HashSet<Entity> {
+        let fans: std::collections::HashSet<_> = user.fans.iter().collect();
+        user.shared
+            .iter()
+            .filter(|ent| fans.contains(ent))
+            .cloned()
+            .collect()
+    };
+    // the ones they share with you or all of them if there's no you
+    let yours = roots
+        .ents(&network)
+        .next()
+        .and_then(|you| users.get(you).ok())
+        .map(|(_, you)| mutuals(you));
+    let theirs = mutuals(user);
+    card.mutuals = card
+        .follows
+        .iter()
+        .filter(|ent| {
+            theirs.contains(ent) && yours.as_ref().is_none_or(|yours| yours.contains(ent))
+        })
+        .cloned()
+        .collect();
+}
+
+fn ego(selected: Res<Selected>, mut ego: ResMut<Ego>, users: Query<(Entity, &User)>) {
+    // the neighbourhood of several users is what they all have in common
+    let mut shared: Option<std::collections::HashSet<Entity>> = None;
+    for ent in selected.iter() {
+        let Ok((_, user)) = users.get(*ent) else {
+            continue;
+        };
+        let mut near: std::collections::HashSet<_> = user.shared.iter().cloned().collect();
+        near.extend(user.fans.iter());
+        shared = Some(match shared {
+            Some(shared) => shared.intersection(&near).cloned().collect(),
+            None => near,
+        });
+    }
+    ego.users = shared.unwrap_or_default();
+    ego.users.extend(selected.iter().cloned());
+}
+
+fn forget(mut route: ResMut<Route>) {
+    route.ends = None;
+    route.path = None;
+}
+
+// breadth first so the first time we reach the end is the shortest way there
+fn walk(
+    mut route: ResMut<Route>,
+    hidden: Res<Hidden>,
+    timeline: Res<Timeline>,
+    users: Query<(Entity, &User)>,
+) {
+    let Some((from, to)) = route.ends else {
+        return;
+    };
+    // only goes the way you can see
+    let mut near: std::collections::HashMap<Entity, Vec<Entity>> = default();
+    for (ent, user) in users.iter().filter(|(ent, _)| !hidden.contains(ent)) {
+        for other in &user.shared {
+            let Ok((_, followed)) = users.get(*other) else {
+                continue;
+            };
+            if hidden.contains(other) || !timeline.linked(user.index, followed.index) {
+                continue;
+            }
+            near.entry(ent).or_default().push(*other);
+            if !route.directed {
+                near.entry(*other).or_default().push(ent);
+            }
+        }
+    }
+    let mut prev = std::collections::HashMap::from([(from, from)]);
+    let mut queue = std::collections::VecDeque::from([from]);
+    while let Some(ent) = queue.pop_front() {
+        if ent == to {
+            break;
+        }
+        for next in near.get(&ent).into_iter().flatten() {
+            if !prev.contains_key(next) {
+                prev.insert(*next, ent);
+                queue.push_back(*next);
+            }
+        }
+    }
+    let path = prev.contains_key(&to).then(|| {
+        let mut path = vec![to];
+        while let Some(ent) = path.last().filter(|ent| **ent != from) {
+            path.push(prev[ent]);
+        }
+        path.reverse();
+        path
+    });
+    // don't trigger change detection on ourselves
+    route.bypass_change_detection().path = path;
+}
+
+fn check(mut card: ResMut<Card>) {
+    match card
+        .task
+        .as_mut()
+        .and_then(|task| bevy::tasks::block_on(bevy::tasks::poll_once(task)))
+    {
+        Some(Ok(profile)) => card.profile = Some(profile.data),
+        Some(Err(e)) => card.err = Some(e.to_string()),
+        None => return,
+    }
+    card.task = None;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn panel(
+    mut ctx: bevy_egui::EguiContexts,
+    mut commands: Commands,
+    mut selected: ResMut<Selected>,
+    mut ego: ResMut<Ego>,
+    mut route: ResMut<Route>,
+    card: Res<Card>,
+    roots: Res<Roots>,
+    network: Res<Network>,
+    mats: Res<Assets<ColorMaterial>>,
+    users: Query<(&User, &MeshMaterial2d<ColorMaterial>)>,
+) {
+    use bevy_egui::egui;
+    let Some((ent, (user, mat))) = selected
+        .last()
+        .and_then(|ent| Some((*ent, users.get(*ent).ok()?)))
+    else {
+        return;
+    };
+    let avatar = mats
+        .get(&mat.0)
+        .and_then(|mat| mat.texture.as_ref())
+        .map(|texture| ctx.add_image(texture.clone_weak()));
+    let Ok(ctx) = ctx.ctx_mut() else { return };
+    let mut pick = None;
+    egui::SidePanel::right("profile").show(ctx, |ui| {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if let Some(avatar) = avatar {
+                    ui.image(egui::load::SizedTexture::new(avatar, [64.0, 64.0]));
+                }
+                ui.vertical(|ui| {
+                    if !user.name().is_empty() {
+                        ui.heading(user.name());
+                    }
+                    ui.label(format!("@{}", user.handle.as_str()));
+                });
+            });
+            match (&card.profile, &card.err) {
+                (Some(data), _) => {
+                    if let Some(bio) = &data.description {
+                        ui.label(bio);
+                    }
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(format!("{} followers", data.followers_count.unwrap_or(0)));
+                        ui.label(format!("{} following", data.follows_count.unwrap_or(0)));
+                        ui.label(format!("{} posts", data.posts_count.unwrap_or(0)));
+                    });
+                    // only filled in when logged in
+                    if let Some(viewer) = &data.viewer {
+                        if viewer.blocking.is_some() {
+                            ui.colored_label(egui::Color32::RED, "you've blocked them");
+                        }
+                        if viewer.blocked_by == Some(true) {
+                            ui.colored_label(egui::Color32::RED, "they've blocked you");
+                        }
+                        if viewer.muted == Some(true) {
+                            ui.label("you've muted them");
+                        }
+                    }
+                }
+                (None, Some(err)) => {
+                    ui.colored_label(egui::Color32::RED, err);
+                }
+                (None, None) => {
+                    ui.spinner();
+                }
+            }
+            let you: Vec<_> = roots.ents(&network).collect();
+            match &you[..] {
+                [you] if *you != ent => {
+                    ui.label(match card.follows.contains(you) {
+                        true => "follows you",
+                        false => "doesn't follow you",
+                    });
+                }
+                [_] => {}
+                // too many to list one by one
+                you if you.len() > 2 => {
+                    let follows = you.iter().filter(|you| card.follows.contains(you)).count();
+                    ui.label(format!("follows {follows} of the {} roots", you.len()));
+                }
+                you => {
+                    for (root, other) in roots.iter().zip(you).filter(|(_, other)| **other != ent) {
+                        ui.label(format!(
+                            "{} @{}",
+                            match card.follows.contains(other) {
+                                true => "follows",
+                                false => "doesn't follow",
+                            },
+                            root.handle.as_str()
+                        ));
+                    }
+                }
+            }
+            ui.horizontal(|ui| {
+                if ui.button("open in bsky.app").clicked() {
+                    let _ = webbrowser::open(&format!(
+                        "https://bsky.app/profile/{}",
+                        user.did.as_str()
+                    ));
+                }
+                if ui.button("focus").clicked() {
+                    commands.trigger_targets(Focus, ent)
+                }
+                if ui.button("close").clicked() {
+                    selected.clear()
+                }
+            });
+            if selected.len() > 1 {
+                ui.label(format!(
+                    "{} selected with {} neighbours in common",
+                    selected.len(),
+                    ego.users.len() - selected.len()
+                ));
+            }
+            ui.label("shift-click to select more");
+            if let [from, to] = selected[..] {
+                ui.horizontal(|ui| {
+                    if ui.button("find path").clicked() {
+                        route.ends = Some((from, to))
+                    }
+                    if ui
+                        .checkbox(&mut route.bypass_change_detection().directed, "directed")
+                        .changed()
+                        && route.ends.is_some()
+                    {
+                        route.set_changed()
+                    }
+                });
+                if route.ends.is_some() {
+                    match &route.path {
+                        Some(path) => {
+                            ui.label(format!("{} hops", path.len() - 1));
+                            ui.horizontal_wrapped(|ui| {
+                                for (i, ent) in path.iter().enumerate() {
+                                    if i != 0 {
+                                        ui.label(if route.directed { "→" } else { "-" });
+                                    }
+                                    let Ok((user, _)) = users.get(*ent) else {
+                                        continue;
+                                    };
+                                    if ui.link(user.handle.as_str()).clicked() {
+                                        commands.trigger_targets(Focus, *ent)
+                                    }
+                                }
+                            });
+                        }
+                        None => {
+                            ui.label("no path");
+                        }
+                    }
+                }
+            }
+            // only reshade when it's actually toggled
+            if ui
+                .checkbox(&mut ego.bypass_change_detection().hide, "hide others")
+                .changed()
+            {
+                ego.set_changed()
+            }
+            for (title, list) in [
+                (
+                    match roots.is_empty() {
+                        true => "mutuals",
+                        false => "mutuals you share",
+                    },
+                    &card.mutuals,
+                ),
+                ("follows", &card.follows),
+                ("followed by", &card.followers),
+            ] {
+                egui::CollapsingHeader::new(format!("{title} ({})", list.len()))
+                    .id_salt(title)
+                    .show(ui, |ui| {
+                        for ent in list {
+                            let Ok((user, _)) = users.get(*ent) else {
+                                continue;
+                            };
+                            if ui.link(user.handle.as_str()).clicked() {
+                                pick = Some(*ent)
+                            }
+                        }
+                    });
+            }
+        });
+    });
+    if let Some(ent) = pick {
+        **selected = vec![ent];
+        commands.trigger_targets(Focus, ent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    // a → b → c → d with a shortcut a → c and e off on their own
+    fn world() -> (World, Vec<Entity>) {
+        let mut world = World::new();
+        world.init_resource::<Hidden>();
+        world.init_resource::<Timeline>();
+        let ents: Vec<_> = (0..5).map(|i| world.spawn(dummy(i)).id()).collect();
+        for (from, to) in [(0, 1), (1, 2), (2, 3), (0, 2)] {
+            world
+                .get_mut::<User>(ents[from])
+                .unwrap()
+                .shared
+                .push(ents[to]);
+        }
+        (world, ents)
+    }
+
+    fn walked(world: &mut World, from: Entity, to: Entity, directed: bool) -> Option<Vec<Entity>> {
+        world.insert_resource(Route {
+            directed,
+            ends: Some((from, to)),
+            path: None,
+        });
+        world.run_system_once(walk).unwrap();
+        world.resource_mut::<Route>().path.take()
+    }
+
+    #[test]
+    fn shortest() {
+        let (mut world, ents) = world();
+        assert_eq!(
+            walked(&mut world, ents[0], ents[3], true),
+            Some(vec![ents[0], ents[2], ents[3]])
+        );
+        assert_eq!(
+            walked(&mut world, ents[1], ents[1], true),
+            Some(vec![ents[1]])
+        );
+    }
+
+    #[test]
+    fn directed() {
+        let (mut world, ents) = world();
+        // follows only go one way
+        assert_eq!(walked(&mut world, ents[3], ents[0], true), None);
+        assert_eq!(
+            walked(&mut world, ents[3], ents[0], false),
+            Some(vec![ents[3], ents[2], ents[0]])
+        );
+        assert_eq!(walked(&mut world, ents[0], ents[4], false), None);
+    }
+
+    #[test]
+    fn unseen() {
+        let (mut world, ents) = world();
+        // everything goes through c so hiding them cuts d off
+        world.resource_mut::<Hidden>().insert(ents[2]);
+        assert_eq!(walked(&mut world, ents[0], ents[3], true), None);
+        world.resource_mut::<Hidden>().clear();
+        // a shortcut that hasn't been made yet means going the long way
+        let mut timeline = Timeline {
+            at: Some(10),
+            ..default()
+        };
+        timeline.links.insert((0, 2), 20);
+        world.insert_resource(timeline);
+        assert_eq!(
+            walked(&mut world, ents[0], ents[3], true),
+            Some(vec![ents[0], ents[1], ents[2], ents[3]])
+        );
+    }
+}
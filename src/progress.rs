@@ -0,0 +1,82 @@
+use super::*;
+
+pub struct Stuff;
+
+impl Plugin for Stuff {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            bevy_egui::EguiPrimaryContextPass,
+            progress.run_if(in_state(Game::Connect)),
+        );
+    }
+}
+
+fn progress(
+    mut ctx: bevy_egui::EguiContexts,
+    mut commands: Commands,
+    time: Res<Time>,
+    progress: Res<Progress>,
+    roots: Res<Roots>,
+    network: Res<Network>,
+) {
+    use bevy_egui::egui;
+    if !progress.fetching && progress.crawling == 0 {
+        return;
+    }
+    let Ok(ctx) = ctx.ctx_mut() else { return };
+    let elapsed = time.elapsed_secs() - progress.since;
+    let rate = progress.requests as f32 / elapsed.max(1.0);
+    egui::Window::new("loading")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 8.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            // the roots aren't their own follows
+            let fetched = network.len() - roots.len();
+            let total: usize = roots
+                .iter()
+                .map(|root| root.follows_count.unwrap_or(0).max(0) as usize)
+                .sum();
+            let listed = progress.listed;
+            if progress.fetching {
+                ui.label(format!(
+                    "fetched {listed} of {total} follows over {} pages",
+                    progress.pages
+                ));
+                ui.add(egui::ProgressBar::new(listed as f32 / total.max(1) as f32));
+            }
+            let finished = progress.done + progress.failed;
+            ui.label(format!(
+                "{} of {fetched} users still being fetched",
+                progress.crawling
+            ));
+            ui.add(egui::ProgressBar::new(
+                finished as f32 / fetched.max(1) as f32,
+            ));
+            if progress.failed > 0 {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("{} users couldn't be fetched", progress.failed),
+                );
+            }
+            if progress.cut > 0 {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("{} roots' follows were cut short", progress.cut),
+                );
+            }
+            ui.label(format!("{rate:.1} requests per second"));
+            let eta = match progress.fetching {
+                true => total.saturating_sub(listed) as f32 * elapsed / listed.max(1) as f32,
+                // users take a different number of requests so go by how fast they finish
+                false => progress.crawling as f32 * elapsed / finished.max(1) as f32,
+            };
+            if progress.requests > 0 {
+                let eta = eta as u64;
+                ui.label(format!("about {}m {}s left", eta / 60, eta % 60));
+            }
+            if ui.button("cancel").clicked() {
+                commands.trigger(Reset)
+            }
+        });
+}
@@ -0,0 +1,133 @@
+use super::*;
+
+pub struct Stuff;
+
+impl Plugin for Stuff {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Search>().add_systems(
+            Update,
+            (
+                // anyone new might match too
+                find.run_if(resource_changed::<Search>.or(added)),
+                shade.run_if(
+                    resource_changed::<Search>
+                        .or(resource_changed::<Ego>)
+                        .or(resource_changed::<Hidden>)
+                        .or(added),
+                ),
+            )
+                .chain()
+                .run_if(in_state(Game::Connect)),
+        );
+    }
+}
+
+fn added(users: Query<(), Added<User>>) -> bool {
+    !users.is_empty()
+}
+
+// a subsequence match where consecutive letters and word starts count for more
+fn score(query: &str, text: &str) -> Option<usize> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut last = None;
+    let mut i = 0;
+    for c in query.to_lowercase().chars() {
+        let found = text[i..].iter().position(|t| *t == c)? + i;
+        score += 1;
+        if last.is_some_and(|last| last + 1 == found) {
+            score += 2;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last = Some(found);
+        i = found + 1;
+    }
+    // shorter texts are closer matches
+    Some(score * 64 / (text.len() + 16))
+}
+
+fn find(mut search: ResMut<Search>, users: Query<(Entity, &User)>) {
+    // don't trigger change detection on ourselves
+    let search = search.bypass_change_detection();
+    let query = search.query.trim().trim_start_matches('@').to_owned();
+    if query.is_empty() {
+        search.matches.clear();
+        return;
+    }
+    let mut scored: Vec<_> = users
+        .iter()
+        .filter_map(|(ent, user)| {
+            let score = score(&query, &user.handle).max(score(&query, user.name()))?;
+            Some((score, ent))
+        })
+        .collect();
+    scored.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+    search.matches = scored.into_iter().map(|(_, ent)| ent).collect();
+}
+
+fn shade(
+    search: Res<Search>,
+    ego: Res<Ego>,
+    hidden: Res<Hidden>,
+    mut mats: ResMut<Assets<ColorMaterial>>,
+    mut users: Query<
+        (
+            Entity,
+            &MeshMaterial2d<ColorMaterial>,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        With<User>,
+    >,
+    added: Query<Entity, Added<User>>,
+) {
+    // otherwise it's just whoever's new that needs shading
+    let all = search.is_changed() || ego.is_changed() || hidden.is_changed();
+    let added: std::collections::HashSet<_> = added.iter().collect();
+    let searching = !search.query.trim().is_empty();
+    let selecting = !ego.users.is_empty();
+    let matches: std::collections::HashSet<_> = search.matches.iter().collect();
+    for (ent, mat, mut trans, mut vis) in &mut users {
+        if !all && !added.contains(&ent) {
+            continue;
+        }
+        let found = matches.contains(&ent);
+        let near = ego.users.contains(&ent);
+        let lit = (!searching || found) && (!selecting || near);
+        *vis = match hidden.contains(&ent) || selecting && ego.hide && !near {
+            true => Visibility::Hidden,
+            false => Visibility::Inherited,
+        };
+        trans.scale = Vec3::splat(if searching && found { 1.5 } else { 1.0 });
+        let Some(mat) = mats.get_mut(&mat.0) else {
+            continue;
+        };
+        mat.color.set_alpha(if lit { 1.0 } else { 0.15 });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subsequence() {
+        assert!(score("spd", "spuds.casa").is_some());
+        assert!(score("SPUDS", "spuds.casa").is_some());
+        // letters have to come in order
+        assert_eq!(score("ba", "abc"), None);
+        assert_eq!(score("abz", "abc"), None);
+    }
+
+    #[test]
+    fn ranking() {
+        // consecutive letters beat spread out ones
+        assert!(score("ab", "abxx") > score("ab", "axbx"));
+        // so do word starts
+        assert!(score("b", "a-bc") > score("b", "abcd"));
+        // and shorter texts
+        assert!(score("sky", "sky") > score("sky", "skyweb enthusiast"));
+    }
+}